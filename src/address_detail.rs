@@ -0,0 +1,95 @@
+use bdk::KeychainKind;
+use iced::{button, Align, Button, Column, Element, HorizontalAlignment, Length, Row, Text};
+
+use rwallet::format::BitcoinUnit;
+use rwallet::wallet_service::AddressHistoryRow;
+use rwallet::AddressDetail;
+use crate::table::{DataTable, TableColumn};
+use crate::theme::{ButtonStyle, Palette};
+use crate::{RuWalletMessage, Table, ROBOTO, ROBOTO_BOLD};
+
+#[derive(Debug, Default)]
+pub struct AddressDetailView {
+    back_button_state: button::State,
+
+    // clicking a transaction opens it
+    pub history_table: DataTable
+}
+
+impl AddressDetailView {
+
+    pub fn view<'a>(&'a mut self, detail: &'a AddressDetail, unit: BitcoinUnit, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let back_button = Button::new(&mut self.back_button_state, Text::new("Back"))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::CloseDetail);
+
+        let detail_title = Text::new("Address")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let keychain = match detail.keychain {
+            KeychainKind::External => "receiving",
+            KeychainKind::Internal => "change",
+        };
+
+        let mut page = Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(detail_title)
+                    .push(back_button)
+            )
+            .push(field("Address", detail.address.clone()))
+            .push(field("Keychain", format!("{} #{}", keychain, detail.index)))
+            .push(field("Derivation Path", detail.derivation_path.clone()))
+            .push(field("Script Type", detail.script_type.clone()))
+            .push(field("scriptPubKey", detail.script_pubkey.clone()));
+
+        let history_title = Text::new(format!("History ({})", detail.history.len()))
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(25)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let columns = [
+            TableColumn::new("Transaction Id", 610, HorizontalAlignment::Left, |row: &AddressHistoryRow| row.txid.clone()),
+            TableColumn::new("Height", 110, HorizontalAlignment::Right, |row: &AddressHistoryRow| {
+                if row.height > 0 { row.height.to_string() } else { "mempool".to_string() }
+            }),
+            TableColumn::new(&format!("Amount ({})", unit), 170, HorizontalAlignment::Right, move |row: &AddressHistoryRow| unit.format_signed(row.amount.into()))
+                .color(move |row: &AddressHistoryRow| palette.amount(row.amount.into())),
+            TableColumn::new(&format!("Balance ({})", unit), 170, HorizontalAlignment::Right, move |row: &AddressHistoryRow| unit.format(row.balance.max(0) as u64)),
+        ];
+
+        page = page
+            .push(history_title)
+            .push(self.history_table.view(Table::AddressHistory, &columns, detail.history.iter().collect(), &|row: &AddressHistoryRow| row.txid.clone(), Vec::new(), palette));
+
+        page.into()
+    }
+}
+
+fn field<'a>(label: &str, value: String) -> Row<'a, RuWalletMessage> {
+    Row::new()
+        .spacing(10)
+        .push(
+            Text::new(label)
+                .font(ROBOTO_BOLD)
+                .width(Length::Units(200))
+                .size(20)
+        )
+        .push(
+            Text::new(value)
+                .font(ROBOTO)
+                .width(Length::Fill)
+                .size(20)
+        )
+}
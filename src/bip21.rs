@@ -0,0 +1,230 @@
+use bdk::bitcoin::{Address, Network};
+
+use std::str::FromStr;
+
+use crate::format::{format_btc, parse_btc};
+
+// BIP21 payment request: `bitcoin:<address>?amount=<btc>&label=<label>&message=<message>`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaymentUri {
+    pub address: String,
+    // sats
+    pub amount: Option<u64>,
+    pub label: Option<String>,
+    pub message: Option<String>
+}
+
+impl PaymentUri {
+
+    pub fn new(address: &str) -> Self {
+        PaymentUri {
+            address: address.to_string(),
+            ..PaymentUri::default()
+        }
+    }
+
+    // accepts a `bitcoin:` URI or a bare address
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+
+        // the scheme is case insensitive
+        let rest = match input.get(..8) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("bitcoin:") => &input[8..],
+            _ => return Ok(PaymentUri::new(input)),
+        };
+
+        let (address, query) = match rest.split_once('?') {
+            Some((address, query)) => (address, query),
+            None => (rest, ""),
+        };
+
+        if address.is_empty() {
+            return Err("The payment URI has no address".to_string());
+        }
+
+        let mut payment_uri = PaymentUri::new(address);
+
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = percent_decode(value)?;
+
+            match key {
+                "amount" => {
+                    let amount = parse_btc(&value).map_err(|e| format!("{} in payment URI", e))?;
+                    payment_uri.amount = Some(amount);
+                },
+                "label" => payment_uri.label = Some(value),
+                "message" => payment_uri.message = Some(value),
+                // we can't honour a required parameter we don't understand, so the whole URI is invalid
+                key if key.starts_with("req-") => {
+                    return Err(format!("The payment URI requires '{}', which is not supported", &key[4..]));
+                },
+                // optional parameters we don't know are ignored
+                _ => {},
+            }
+        }
+
+        Ok(payment_uri)
+    }
+
+    // describes why the address can't be paid from a wallet on `network`
+    pub fn network_warning(&self, network: Network) -> Option<String> {
+        match Address::from_str(&self.address) {
+            Ok(address) if address.network != network => {
+                Some(format!("The address is for {} but the wallet is on {}", address.network, network))
+            },
+            Ok(_) => None,
+            Err(e) => Some(format!("Invalid address {}: {}", self.address, e)),
+        }
+    }
+
+    // the bare address without optional fields, a full URI otherwise
+    pub fn to_uri(&self) -> String {
+        let mut params = Vec::<String>::new();
+
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", format_btc(amount)));
+        }
+
+        if let Some(label) = self.label.as_ref().filter(|label| !label.is_empty()) {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+
+        if let Some(message) = self.message.as_ref().filter(|message| !message.is_empty()) {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+
+        if params.is_empty() {
+            return self.address.clone();
+        }
+
+        format!("bitcoin:{}?{}", self.address, params.join("&"))
+    }
+}
+
+// RFC 3986 unreserved characters are kept, everything else is escaped
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::<u8>::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3).ok_or("Truncated escape in payment URI")?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| format!("Invalid escape %{} in payment URI", hex))?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| "Payment URI is not valid UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET: &str = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+    const TESTNET: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    fn parse(query: &str) -> Result<PaymentUri, String> {
+        PaymentUri::parse(&format!("bitcoin:{}?{}", MAINNET, query))
+    }
+
+    #[test]
+    fn parses_the_bip21_example() {
+        let payment_uri = parse("amount=20.3&label=Luke-Jr").unwrap();
+
+        assert_eq!(payment_uri.address, MAINNET);
+        assert_eq!(payment_uri.amount, Some(2_030_000_000));
+        assert_eq!(payment_uri.label.as_deref(), Some("Luke-Jr"));
+        assert_eq!(payment_uri.message, None);
+    }
+
+    #[test]
+    fn accepts_a_bare_address_and_any_scheme_case() {
+        assert_eq!(PaymentUri::parse(&format!(" {} ", MAINNET)).unwrap(), PaymentUri::new(MAINNET));
+        assert_eq!(PaymentUri::parse(&format!("BITCOIN:{}", MAINNET)).unwrap(), PaymentUri::new(MAINNET));
+        assert!(PaymentUri::parse("bitcoin:?amount=1").is_err());
+    }
+
+    #[test]
+    fn unknown_required_parameters_are_fatal() {
+        let error = parse("amount=1&req-somethingyoudontunderstand=50").unwrap_err();
+
+        assert!(error.contains("somethingyoudontunderstand"), "{}", error);
+    }
+
+    #[test]
+    fn unknown_optional_parameters_are_ignored() {
+        let payment_uri = parse("somethingyoudontunderstand=50&somethingelseyoudontget=999&amount=1").unwrap();
+
+        assert_eq!(payment_uri.amount, Some(100_000_000));
+    }
+
+    #[test]
+    fn percent_escapes_are_decoded() {
+        let payment_uri = parse("label=Caf%C3%A9%20%22Le%20Coin%22&message=Order%2342").unwrap();
+
+        assert_eq!(payment_uri.label.as_deref(), Some("Café \"Le Coin\""));
+        assert_eq!(payment_uri.message.as_deref(), Some("Order#42"));
+
+        assert!(parse("label=100%zz").is_err());
+        assert!(parse("label=100%4").is_err());
+        assert!(parse("label=%FF").is_err());
+    }
+
+    #[test]
+    fn amounts_are_decimal_btc_with_at_most_8_places() {
+        assert_eq!(parse("amount=0.00000001").unwrap().amount, Some(1));
+        assert_eq!(parse("amount=.5").unwrap().amount, Some(50_000_000));
+        assert_eq!(parse("amount=21").unwrap().amount, Some(2_100_000_000));
+
+        assert!(parse("amount=0.000000001").is_err());
+        assert!(parse("amount=1,5").is_err());
+        assert!(parse("amount=-1").is_err());
+        assert!(parse("amount=1e3").is_err());
+        assert!(parse("amount=").is_err());
+    }
+
+    #[test]
+    fn warns_about_an_address_of_another_network() {
+        assert_eq!(PaymentUri::new(MAINNET).network_warning(Network::Bitcoin), None);
+        assert_eq!(PaymentUri::new(TESTNET).network_warning(Network::Testnet), None);
+
+        assert!(PaymentUri::new(MAINNET).network_warning(Network::Testnet).is_some());
+        assert!(PaymentUri::new(TESTNET).network_warning(Network::Bitcoin).is_some());
+        assert!(PaymentUri::new("not an address").network_warning(Network::Bitcoin).is_some());
+    }
+
+    #[test]
+    fn uri_round_trips() {
+        let payment_uri = PaymentUri {
+            address: MAINNET.to_string(),
+            amount: Some(150_000),
+            label: Some("Rent & bills".to_string()),
+            message: Some("May 100%".to_string())
+        };
+
+        let uri = payment_uri.to_uri();
+
+        assert_eq!(uri, format!("bitcoin:{}?amount=0.0015&label=Rent%20%26%20bills&message=May%20100%25", MAINNET));
+        assert_eq!(PaymentUri::parse(&uri).unwrap(), payment_uri);
+        assert_eq!(PaymentUri::new(MAINNET).to_uri(), MAINNET);
+    }
+}
@@ -0,0 +1,103 @@
+use bdk::bitcoin::Script;
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::stream::BoxStream;
+use iced::Subscription;
+use iced_native::subscription::Recipe;
+use rwallet::electrum::{ElectrumConnector, FailoverConnector, ServerSettings};
+use rwallet::watcher::{ChainEvent, ChainWatcher};
+
+use std::any::TypeId;
+use std::hash::{Hash, Hasher};
+use std::thread;
+use std::time::Duration;
+
+// how often pending notifications are collected
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// wait before subscribing again after the connection broke
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+// live notifications for the scripts of a wallet, restarted by iced whenever the watched scripts change
+pub fn watch(wallet_id: u32, servers: Vec<ServerSettings>, scripts: Vec<Script>) -> Subscription<ChainEvent> {
+    Subscription::from_recipe(ChainSubscription {
+        wallet_id,
+        servers,
+        scripts
+    })
+}
+
+struct ChainSubscription {
+    wallet_id: u32,
+    servers: Vec<ServerSettings>,
+    scripts: Vec<Script>
+}
+
+impl<H, I> Recipe<H, I> for ChainSubscription
+where
+    H: Hasher
+{
+    type Output = ChainEvent;
+
+    fn hash(&self, state: &mut H) {
+        TypeId::of::<Self>().hash(state);
+        self.wallet_id.hash(state);
+        // any change to the proxy, the TLS options or the fallback servers needs a new connection
+        self.servers.hash(state);
+
+        for script in &self.scripts {
+            script.as_bytes().hash(state);
+        }
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<'static, I>) -> BoxStream<'static, Self::Output> {
+        let (sender, receiver) = mpsc::unbounded();
+
+        // the electrum client blocks, so it gets its own thread; it stops once iced drops the receiver
+        thread::spawn(move || run(*self, sender));
+
+        Box::pin(receiver)
+    }
+}
+
+fn run(subscription: ChainSubscription, sender: UnboundedSender<ChainEvent>) {
+    let connector = FailoverConnector::new(subscription.servers);
+    let mut reconnecting = false;
+
+    while !sender.is_closed() {
+        let watcher = match ChainWatcher::subscribe(&connector, subscription.scripts.clone()) {
+            Ok((watcher, tip)) => {
+                // catch up on whatever happened while we were offline
+                if reconnecting {
+                    let _ = sender.unbounded_send(ChainEvent::NewTip(tip));
+                    let _ = sender.unbounded_send(ChainEvent::ScriptsChanged(subscription.scripts.clone()));
+                }
+                watcher
+            },
+            Err(e) => {
+                let _ = sender.unbounded_send(ChainEvent::Disconnected(e.to_string()));
+                thread::sleep(RETRY_INTERVAL);
+                continue;
+            },
+        };
+
+        reconnecting = true;
+
+        while !sender.is_closed() {
+            thread::sleep(POLL_INTERVAL);
+
+            match watcher.poll() {
+                Ok(events) => {
+                    for event in events {
+                        if sender.unbounded_send(event).is_err() {
+                            return;
+                        }
+                    }
+                },
+                Err(e) => {
+                    let _ = sender.unbounded_send(ChainEvent::Disconnected(e.to_string()));
+                    connector.invalidate();
+                    break;
+                },
+            }
+        }
+    }
+}
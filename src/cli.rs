@@ -0,0 +1,331 @@
+use bdk::bitcoin::consensus::encode::serialize_hex;
+use bdk::bitcoin::util::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::Address;
+use bdk::KeychainKind;
+use rwallet::config::{AppConfig, WalletConfig};
+use rwallet::export::{self, DateRange, ExportFormat};
+use rwallet::wallet_service::parse_transaction;
+use rwallet::WalletService;
+use serde_json::json;
+
+use std::fs;
+use std::str::FromStr;
+
+const USAGE: &str = "Usage: rwallet --cli [--wallet <name>] [--json] <command> [args]
+
+Commands:
+    sync                                    sync the wallet with the Electrum server
+    balance                                 print the balance, unconfirmed funds included
+    addresses [--count <n>] [--change]      list addresses with balance and tx count
+    utxos                                   list unspent outputs
+    transactions                            list wallet transactions
+    new-address                             reveal a new receive address
+    send <address> <amount> [--fee-rate <sat/vb>]
+                                            create, sign and broadcast a transaction,
+                                            prints the PSBT if it cannot be fully signed
+    psbt-sign <psbt>                        sign a base64 PSBT with the wallet keys
+    broadcast <psbt|raw tx hex>             broadcast a finalized PSBT or a raw transaction
+    export <transactions|addresses|utxos> [--format csv|json] [--from <YYYY-MM-DD>]
+           [--to <YYYY-MM-DD>] [--count <n>] [--output <file>]
+                                            export a list, to stdout unless --output is given;
+                                            the date range only applies to transactions
+
+The wallet defaults to the one selected in the GUI.";
+
+struct CliOptions {
+    wallet_name: Option<String>,
+    json: bool,
+    command: String,
+    args: Vec<String>
+}
+
+// returns the process exit code
+pub fn run(args: &[String]) -> i32 {
+
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    match execute(&options) {
+        Ok(()) => 0,
+        Err(e) => {
+            if options.json {
+                println!("{}", json!({ "error": e }));
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            1
+        }
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<CliOptions, String> {
+    let mut wallet_name = None;
+    let mut json = false;
+    let mut positional = Vec::<String>::new();
+
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--wallet" => {
+                wallet_name = Some(iter.next().ok_or("--wallet requires a value")?.clone());
+            },
+            "--json" => json = true,
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if positional.is_empty() {
+        return Err("missing command".to_string());
+    }
+
+    let command = positional.remove(0);
+
+    Ok(CliOptions {
+        wallet_name,
+        json,
+        command,
+        args: positional
+    })
+}
+
+fn execute(options: &CliOptions) -> Result<(), String> {
+    let wallet_config = find_wallet_config(options.wallet_name.as_deref())?;
+
+    if wallet_config.external_descriptor.is_empty() {
+        return Err(format!("wallet '{}' has no descriptor", wallet_config.name));
+    }
+
+    let wallet_service = WalletService::restore(&wallet_config).map_err(|e| e.to_string())?;
+
+    if wallet_config.paranoid {
+        for warning in wallet_service.cross_check(5).map_err(|e| e.to_string())? {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+
+    let args = &options.args;
+    let json = options.json;
+
+    match options.command.as_str() {
+        "sync" => {
+            print_output(json, json!({ "synced": true }), format!("Wallet '{}' synced", wallet_config.name));
+        },
+        "balance" => {
+            let balance = wallet_service.balance().map_err(|e| e.to_string())?;
+            print_output(json, json!({ "balance": balance }), balance.to_string());
+        },
+        "addresses" => {
+            let count: u32 = match flag_value(args, "--count") {
+                Some(value) => value.parse().map_err(|_| "invalid --count")?,
+                None => 10,
+            };
+            let keychain = if args.iter().any(|a| a == "--change") {
+                KeychainKind::Internal
+            } else {
+                KeychainKind::External
+            };
+
+            let address_items = wallet_service.addresses(keychain, count).map_err(|e| e.to_string())?;
+
+            let rows: Vec<_> = address_items.iter().map(|addr_item| json!({
+                "index": addr_item.index,
+                "address": addr_item.address,
+                "balance": addr_item.balance,
+                "tx_count": addr_item.tx_count
+            })).collect();
+
+            let text = address_items.iter()
+                .map(|addr_item| format!("{}\t{}\t{}\t{}", addr_item.index, addr_item.address, addr_item.balance, addr_item.tx_count))
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            print_output(json, json!(rows), text);
+        },
+        "utxos" => {
+            let utxo_items = wallet_service.unspent().map_err(|e| e.to_string())?;
+
+            let rows: Vec<_> = utxo_items.iter().map(|utxo_item| json!({
+                "txid": utxo_item.txid,
+                "vout": utxo_item.vout,
+                "address": utxo_item.address,
+                "amount": utxo_item.amount,
+                "height": utxo_item.height
+            })).collect();
+
+            let text = utxo_items.iter()
+                .map(|utxo_item| format!("{}:{}\t{}\t{}", utxo_item.txid, utxo_item.vout, utxo_item.address, utxo_item.amount))
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            print_output(json, json!(rows), text);
+        },
+        "transactions" => {
+            let transaction_items = wallet_service.transactions().map_err(|e| e.to_string())?;
+
+            let rows: Vec<_> = transaction_items.iter().map(|transaction_item| json!({
+                "txid": transaction_item.txid,
+                "amount": transaction_item.amount as i64,
+                "fee": transaction_item.fee,
+                "height": transaction_item.height
+            })).collect();
+
+            let text = transaction_items.iter()
+                .map(|transaction_item| format!("{}\t{}\t{}", transaction_item.txid, transaction_item.amount, transaction_item.height))
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            print_output(json, json!(rows), text);
+        },
+        "new-address" => {
+            let address = wallet_service.new_address().map_err(|e| e.to_string())?;
+
+            print_output(json, json!({ "address": address }), address);
+        },
+        "send" => {
+            let address = args.get(0).ok_or("send requires an address")?;
+            let amount: u64 = args.get(1).ok_or("send requires an amount")?
+                .parse().map_err(|_| "invalid amount")?;
+
+            let address = Address::from_str(address).map_err(|e| e.to_string())?;
+
+            let fee_rate = match flag_value(args, "--fee-rate") {
+                Some(fee_rate) => Some(fee_rate.parse::<f32>().map_err(|_| "invalid --fee-rate")?),
+                None => None,
+            };
+
+            let (mut psbt, _details) = wallet_service.create_psbt(&[(address, amount)], fee_rate).map_err(|e| e.to_string())?;
+
+            let finalized = wallet_service.sign(&mut psbt).map_err(|e| e.to_string())?;
+
+            if finalized {
+                let tx = psbt.extract_tx();
+                let txid = wallet_service.broadcast(&tx).map_err(|e| e.to_string())?;
+
+                print_output(json, json!({ "txid": txid.to_string() }), txid.to_string());
+            } else {
+                print_output(json,
+                    json!({ "psbt": psbt.to_string(), "finalized": false }),
+                    psbt.to_string());
+            }
+        },
+        "psbt-sign" => {
+            let psbt = args.get(0).ok_or("psbt-sign requires a PSBT")?;
+            let mut psbt = PartiallySignedTransaction::from_str(psbt).map_err(|e| e.to_string())?;
+
+            let finalized = wallet_service.sign(&mut psbt).map_err(|e| e.to_string())?;
+
+            print_output(json,
+                json!({ "psbt": psbt.to_string(), "finalized": finalized }),
+                psbt.to_string());
+        },
+        "broadcast" => {
+            let input = args.get(0).ok_or("broadcast requires a PSBT or a raw transaction")?;
+
+            let tx = parse_transaction(input)?;
+            let txid = wallet_service.broadcast(&tx).map_err(|e| e.to_string())?;
+
+            print_output(json, json!({ "txid": txid.to_string(), "hex": serialize_hex(&tx) }), txid.to_string());
+        },
+        "export" => {
+            let list = args.get(0).ok_or("export requires transactions, addresses or utxos")?;
+
+            let format = match flag_value(args, "--format").map(String::as_str) {
+                None | Some("csv") => ExportFormat::Csv,
+                Some("json") => ExportFormat::Json,
+                Some(other) => return Err(format!("unknown format '{}'", other)),
+            };
+
+            let content = match list.as_str() {
+                "transactions" => {
+                    let range = DateRange::parse(
+                        flag_value(args, "--from").map(String::as_str).unwrap_or(""),
+                        flag_value(args, "--to").map(String::as_str).unwrap_or("")
+                    )?;
+
+                    let tip_height = wallet_service.tip_height().map_err(|e| e.to_string())?;
+                    let history = wallet_service.history(tip_height).map_err(|e| e.to_string())?;
+
+                    export::export_history(&history, &range, format)
+                },
+                "addresses" => {
+                    let count: u32 = match flag_value(args, "--count") {
+                        Some(value) => value.parse().map_err(|_| "invalid --count")?,
+                        None => 10,
+                    };
+
+                    let receiving = wallet_service.addresses(KeychainKind::External, count).map_err(|e| e.to_string())?;
+                    let change = wallet_service.addresses(KeychainKind::Internal, count).map_err(|e| e.to_string())?;
+
+                    export::export_addresses(&receiving, &change, format)
+                },
+                "utxos" => {
+                    let utxo_items = wallet_service.unspent().map_err(|e| e.to_string())?;
+
+                    export::export_utxos(&utxo_items, format)
+                },
+                other => return Err(format!("cannot export '{}'", other)),
+            };
+
+            match flag_value(args, "--output") {
+                Some(path) => {
+                    fs::write(path, content).map_err(|e| format!("cannot write {}: {}", path, e))?;
+                    print_output(json, json!({ "exported": path }), format!("Exported to {}", path));
+                },
+                None => println!("{}", content),
+            }
+        },
+        other => return Err(format!("unknown command '{}'", other)),
+    }
+
+    Ok(())
+}
+
+pub fn find_wallet_config(name: Option<&str>) -> Result<WalletConfig, String> {
+    let mut config = AppConfig::load()?;
+
+    let id = match name {
+        Some(name) => config.wallets.iter().find(|w| w.name == name).map(|w| w.id),
+        None => config.selected,
+    };
+
+    let wallet_config = match (id.and_then(|id| config.wallet_mut(id)), name) {
+        (Some(wallet_config), _) => wallet_config,
+        (None, Some(name)) => return Err(format!("no wallet named '{}'", name)),
+        (None, None) => return Err("no wallet selected, use --wallet <name>".to_string()),
+    };
+
+    let learned = wallet_config.verify_server().map_err(|e| e.to_string())?;
+    let wallet_config = wallet_config.clone();
+
+    if learned {
+        eprintln!("Trusting certificate {} of {} on first use", wallet_config.tls.fingerprint, wallet_config.electrum_url);
+        config.save().map_err(|e| format!("cannot save the config: {}", e))?;
+    }
+
+    Ok(wallet_config)
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+}
+
+fn print_output(json: bool, value: serde_json::Value, text: String) {
+    if json {
+        println!("{}", value);
+    } else {
+        println!("{}", text);
+    }
+}
@@ -0,0 +1,224 @@
+use bdk::bitcoin::Network;
+use bdk::electrum_client;
+use serde::{Deserialize, Serialize};
+
+use crate::electrum::{ElectrumPool, ProxyConfig, ServerSettings};
+use crate::format::BitcoinUnit;
+use crate::notifications::NotificationSettings;
+use crate::prices::FiatSettings;
+use crate::tls::{CertificateCheck, TlsConfig};
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+pub const DEFAULT_ELECTRUM_URL: &str = "ssl://electrum.blockstream.info:60002";
+
+// Colours of the whole application, the palettes live with the GUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    Light,
+    Dark
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::Light
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Light => write!(f, "Light"),
+            Theme::Dark => write!(f, "Dark"),
+        }
+    }
+}
+
+pub static THEMES: [Theme; 2] = [Theme::Light, Theme::Dark];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletConfig {
+    // stable identifier used for the on-disk database, so renaming a wallet doesn't move it
+    pub id: u32,
+    pub name: String,
+    pub external_descriptor: String,
+    pub internal_descriptor: String,
+    pub network: Network,
+    pub electrum_url: String,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    // tried in order when `electrum_url` is unreachable
+    #[serde(default)]
+    pub fallback_urls: Vec<String>,
+    // cross-check the primary server against a fallback one
+    #[serde(default)]
+    pub paranoid: bool
+}
+
+impl WalletConfig {
+    pub fn new(id: u32, name: &str) -> Self {
+        WalletConfig {
+            id,
+            name: name.to_string(),
+            external_descriptor: String::new(),
+            internal_descriptor: String::new(),
+            network: Network::Testnet,
+            electrum_url: DEFAULT_ELECTRUM_URL.to_string(),
+            proxy: ProxyConfig::default(),
+            tls: TlsConfig::default(),
+            fallback_urls: Vec::new(),
+            paranoid: false
+        }
+    }
+
+    pub fn server_settings(&self) -> ServerSettings {
+        ServerSettings {
+            url: self.electrum_url.clone(),
+            proxy: self.proxy.clone(),
+            tls: self.tls.clone()
+        }
+    }
+
+    // primary server first, then the fallbacks; TLS pinning only applies to the primary one
+    pub fn server_list(&self) -> Vec<ServerSettings> {
+        let mut servers = vec![self.server_settings()];
+
+        for url in &self.fallback_urls {
+            servers.push(ServerSettings {
+                url: url.clone(),
+                proxy: self.proxy.clone(),
+                tls: TlsConfig::default()
+            });
+        }
+
+        servers
+    }
+
+    // checks the server certificate, returns true if a first use fingerprint was learned and should be saved
+    pub fn verify_server(&mut self) -> Result<bool, electrum_client::Error> {
+        match ElectrumPool::global().check_certificate(&self.server_settings())? {
+            CertificateCheck::FirstUse(fingerprint) => {
+                self.tls.fingerprint = fingerprint;
+                Ok(true)
+            },
+            CertificateCheck::Trusted => Ok(false),
+        }
+    }
+
+    pub fn database_path(&self) -> PathBuf {
+        data_dir().join("wallets").join(self.id.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppConfig {
+    pub wallets: Vec<WalletConfig>,
+    pub selected: Option<u32>,
+    next_id: u32,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    // unit of every amount shown or typed in
+    #[serde(default)]
+    pub unit: BitcoinUnit,
+    #[serde(default)]
+    pub fiat: FiatSettings,
+    #[serde(default)]
+    pub theme: Theme
+}
+
+impl AppConfig {
+
+    // no config file yet is a fresh start, an unreadable one is an error
+    pub fn load() -> Result<Self, String> {
+        let path = config_path();
+
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AppConfig::default()),
+            Err(e) => Err(format!("Cannot read config file {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self).expect("config is always serializable");
+
+        fs::write(&path, content)
+    }
+
+    pub fn create_wallet(&mut self, name: &str) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.wallets.push(WalletConfig::new(id, name));
+        self.selected = Some(id);
+
+        id
+    }
+
+    pub fn rename_wallet(&mut self, id: u32, name: &str) {
+        if let Some(wallet) = self.wallet_mut(id) {
+            wallet.name = name.to_string();
+        }
+    }
+
+    // the wallet is kept when its database cannot be removed
+    pub fn delete_wallet(&mut self, id: u32) -> std::io::Result<()> {
+        if let Some(wallet) = self.wallet(id) {
+            let db_path = wallet.database_path();
+            if db_path.exists() {
+                fs::remove_dir_all(&db_path)?;
+            }
+        }
+
+        self.wallets.retain(|w| w.id != id);
+
+        if self.selected == Some(id) {
+            self.selected = self.wallets.first().map(|w| w.id);
+        }
+
+        Ok(())
+    }
+
+    pub fn wallet(&self, id: u32) -> Option<&WalletConfig> {
+        self.wallets.iter().find(|w| w.id == id)
+    }
+
+    pub fn wallet_mut(&mut self, id: u32) -> Option<&mut WalletConfig> {
+        self.wallets.iter_mut().find(|w| w.id == id)
+    }
+
+    pub fn selected_wallet(&self) -> Option<&WalletConfig> {
+        self.selected.and_then(|id| self.wallet(id))
+    }
+
+    pub fn selected_wallet_mut(&mut self) -> Option<&mut WalletConfig> {
+        match self.selected {
+            Some(id) => self.wallet_mut(id),
+            None => None,
+        }
+    }
+}
+
+pub fn data_dir() -> PathBuf {
+    dirs_next::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rwallet")
+}
+
+fn config_path() -> PathBuf {
+    dirs_next::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rwallet")
+        .join("config.json")
+}
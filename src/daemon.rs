@@ -0,0 +1,338 @@
+use bdk::bitcoin::base64;
+use bdk::bitcoin::hashes::hex::ToHex;
+use bdk::bitcoin::Address;
+use rand::Rng;
+use rwallet::config;
+use rwallet::wallet_service::parse_transaction;
+use rwallet::WalletService;
+use serde_json::{json, Value};
+use tiny_http::{Header, Response, Server};
+
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::cli;
+
+const DEFAULT_RPC_BIND: &str = "127.0.0.1:18350";
+const DEFAULT_SYNC_INTERVAL_SECS: u64 = 60;
+
+const USAGE: &str = "Usage: rwallet --daemon [--wallet <name>] [--rpc-bind <host:port>] [--sync-interval <secs>]
+
+Serves JSON-RPC 2.0 over HTTP POST. Clients authenticate with HTTP basic auth
+using the user:password pair stored in the cookie file printed on startup.
+
+Methods:
+    getbalance
+    getnewaddress
+    listunspent
+    listtransactions
+    createpsbt [{\"<address>\": <amount>, ...}, <fee rate sat/vb>]
+    broadcast [<psbt|raw tx hex>]";
+
+type SharedWallet = Arc<Mutex<WalletService>>;
+
+struct DaemonOptions {
+    wallet_name: Option<String>,
+    rpc_bind: String,
+    sync_interval: Duration
+}
+
+struct RpcError {
+    code: i64,
+    message: String
+}
+
+impl RpcError {
+    fn invalid_params(message: &str) -> Self {
+        RpcError { code: -32602, message: message.to_string() }
+    }
+
+    fn wallet(message: String) -> Self {
+        RpcError { code: -4, message }
+    }
+}
+
+// returns the process exit code
+pub fn run(args: &[String]) -> i32 {
+
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let wallet_config = match cli::find_wallet_config(options.wallet_name.as_deref()) {
+        Ok(wallet_config) => wallet_config,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 1;
+        }
+    };
+
+    let server = match Server::http(&options.rpc_bind) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Error: cannot bind {}: {}", options.rpc_bind, e);
+            return 1;
+        }
+    };
+
+    let cookie = match write_cookie(wallet_config.id) {
+        Ok(cookie) => cookie,
+        Err(e) => {
+            eprintln!("Error: cannot write cookie file: {}", e);
+            return 1;
+        }
+    };
+
+    println!("Syncing wallet '{}' ...", wallet_config.name);
+
+    let wallet = match WalletService::restore(&wallet_config) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            eprintln!("Error: cannot restore wallet: {}", e);
+            return 1;
+        }
+    };
+
+    let sync_wallet = match wallet.sync_handle() {
+        Ok(sync_wallet) => sync_wallet,
+        Err(e) => {
+            eprintln!("Error: cannot open the background sync connection: {}", e);
+            return 1;
+        }
+    };
+
+    let wallet = Arc::new(Mutex::new(wallet));
+
+    spawn_sync_loop(sync_wallet, options.sync_interval);
+
+    println!("JSON-RPC server listening on {}", options.rpc_bind);
+    println!("Cookie file: {}", cookie_path(wallet_config.id).display());
+
+    for mut request in server.incoming_requests() {
+
+        if !is_authorized(request.headers(), &cookie) {
+            let response = Response::from_string("Unauthorized")
+                .with_status_code(401)
+                .with_header(Header::from_bytes(&b"WWW-Authenticate"[..], &b"Basic realm=\"rwallet\""[..]).unwrap());
+            let _ = request.respond(response);
+            continue;
+        }
+
+        let mut body = String::new();
+        let reply = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle_request(&wallet, &body),
+            Err(e) => error_reply(Value::Null, RpcError { code: -32700, message: e.to_string() }),
+        };
+
+        let response = Response::from_string(reply.to_string())
+            .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap());
+
+        if let Err(e) = request.respond(response) {
+            println!("Cannot send response: {}", e);
+        }
+    }
+
+    0
+}
+
+fn parse_options(args: &[String]) -> Result<DaemonOptions, String> {
+    let mut options = DaemonOptions {
+        wallet_name: None,
+        rpc_bind: DEFAULT_RPC_BIND.to_string(),
+        sync_interval: Duration::from_secs(DEFAULT_SYNC_INTERVAL_SECS)
+    };
+
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--wallet" => {
+                options.wallet_name = Some(iter.next().ok_or("--wallet requires a value")?.clone());
+            },
+            "--rpc-bind" => {
+                options.rpc_bind = iter.next().ok_or("--rpc-bind requires a value")?.clone();
+            },
+            "--sync-interval" => {
+                let secs: u64 = iter.next().ok_or("--sync-interval requires a value")?
+                    .parse().map_err(|_| "invalid --sync-interval")?;
+                options.sync_interval = Duration::from_secs(secs);
+            },
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+
+    Ok(options)
+}
+
+// syncs through its own handle on the wallet database, RPC calls keep being answered meanwhile
+fn spawn_sync_loop(wallet: WalletService, interval: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+
+        if let Err(e) = wallet.sync() {
+            println!("Background sync failed: {}", e);
+        }
+    });
+}
+
+// one cookie per wallet, so daemons serving different wallets don't overwrite each other's
+fn cookie_path(wallet_id: u32) -> PathBuf {
+    config::data_dir().join(format!(".cookie-{}", wallet_id))
+}
+
+// writes a fresh random cookie and returns the expected basic auth credentials
+fn write_cookie(wallet_id: u32) -> std::io::Result<String> {
+    let secret: [u8; 32] = rand::thread_rng().gen();
+    let cookie = format!("__cookie__:{}", secret.to_hex());
+
+    let path = cookie_path(wallet_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    // readable by the owner only from the moment it exists
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&path)?;
+
+    // `mode` only applies to a new file, an older cookie may have been created wider
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(cookie.as_bytes())?;
+
+    Ok(cookie)
+}
+
+fn is_authorized(headers: &[Header], cookie: &str) -> bool {
+    let expected = format!("Basic {}", base64::encode(cookie));
+
+    headers.iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .map(|h| constant_time_eq(h.value.as_str().as_bytes(), expected.as_bytes()))
+        .unwrap_or(false)
+}
+
+// looks at every byte whatever the first difference, so the response time says nothing about the cookie
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn handle_request(wallet: &SharedWallet, body: &str) -> Value {
+    let request: Value = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return error_reply(Value::Null, RpcError { code: -32700, message: e.to_string() }),
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+    let method = match request.get("method").and_then(Value::as_str) {
+        Some(method) => method,
+        None => return error_reply(id, RpcError { code: -32600, message: "missing method".to_string() }),
+    };
+
+    let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+
+    let wallet = wallet.lock().unwrap();
+
+    match dispatch(&wallet, method, &params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_reply(id, e),
+    }
+}
+
+fn dispatch(wallet: &WalletService, method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "getbalance" => {
+            let balance = wallet.balance().map_err(|e| RpcError::wallet(e.to_string()))?;
+            Ok(json!(balance))
+        },
+        "getnewaddress" => {
+            let address = wallet.new_address().map_err(|e| RpcError::wallet(e.to_string()))?;
+            Ok(json!(address))
+        },
+        "listunspent" => {
+            let utxo_items = wallet.unspent().map_err(|e| RpcError::wallet(e.to_string()))?;
+
+            Ok(json!(utxo_items.iter().map(|utxo_item| json!({
+                "txid": utxo_item.txid,
+                "vout": utxo_item.vout,
+                "address": utxo_item.address,
+                "amount": utxo_item.amount,
+                "height": utxo_item.height
+            })).collect::<Vec<Value>>()))
+        },
+        "listtransactions" => {
+            let transaction_items = wallet.transactions().map_err(|e| RpcError::wallet(e.to_string()))?;
+
+            Ok(json!(transaction_items.iter().map(|transaction_item| json!({
+                "txid": transaction_item.txid,
+                "amount": transaction_item.amount as i64,
+                "fee": transaction_item.fee,
+                "height": transaction_item.height
+            })).collect::<Vec<Value>>()))
+        },
+        "createpsbt" => {
+            let outputs = params.get(0)
+                .and_then(Value::as_object)
+                .ok_or_else(|| RpcError::invalid_params("expected an object of address to amount"))?;
+
+            let mut recipients = Vec::<(Address, u64)>::new();
+
+            for (address, amount) in outputs {
+                let address = Address::from_str(address).map_err(|e| RpcError::invalid_params(&e.to_string()))?;
+                let amount = amount.as_u64().ok_or_else(|| RpcError::invalid_params("amount must be in sats"))?;
+
+                recipients.push((address, amount));
+            }
+
+            let fee_rate = params.get(1).and_then(Value::as_f64).map(|fee_rate| fee_rate as f32);
+
+            let (psbt, details) = wallet.create_psbt(&recipients, fee_rate).map_err(|e| RpcError::wallet(e.to_string()))?;
+
+            Ok(json!({ "psbt": psbt.to_string(), "fee": details.fee }))
+        },
+        "broadcast" => {
+            let input = params.get(0)
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError::invalid_params("expected a PSBT or a raw transaction"))?;
+
+            let tx = parse_transaction(input).map_err(|e| RpcError::invalid_params(&e))?;
+            let txid = wallet.broadcast(&tx).map_err(|e| RpcError::wallet(e.to_string()))?;
+
+            Ok(json!(txid.to_string()))
+        },
+        _ => Err(RpcError { code: -32601, message: format!("method '{}' not found", method) }),
+    }
+}
+
+fn error_reply(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message }
+    })
+}
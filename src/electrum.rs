@@ -0,0 +1,396 @@
+use bdk::bitcoin::{Script, Txid};
+use bdk::electrum_client::{Client, ConfigBuilder, ElectrumApi, Error, Socks5Config};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::tls::{self, CertificateCheck, TlsConfig, TlsRelay};
+
+static POOL: Lazy<Arc<ElectrumPool>> = Lazy::new(|| Arc::new(ElectrumPool::new(RetryPolicy::default())));
+
+// Hands out Electrum clients. The wallet and the address queries only talk to
+// the server through a connector, so tests can point them at a mock server.
+pub trait ElectrumConnector: Send + Sync {
+    // dedicated client, owned by the wallet's blockchain backend
+    fn connect(&self) -> Result<Client, Error>;
+
+    // shared client for ad-hoc queries
+    fn client(&self) -> Result<Arc<Client>, Error>;
+
+    // drops a shared client that stopped answering, the next `client` call reconnects
+    fn invalidate(&self) {}
+
+    // drops the shared clients of every server, their connection settings are about to change
+    fn close(&self) {
+        self.invalidate()
+    }
+
+    // client of another server, to cross-check what the main one tells us
+    fn secondary_client(&self) -> Option<Result<Arc<Client>, Error>> {
+        None
+    }
+
+    fn url(&self) -> &str;
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub timeout_secs: u8,
+    // attempts made by the client before giving up on a request
+    pub retries: u8
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            timeout_secs: 30,
+            retries: 2
+        }
+    }
+}
+
+// SOCKS5 proxy (e.g. a local Tor daemon on 127.0.0.1:9050) used to reach the server.
+#[derive(Debug, Clone, Default, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    // host:port, empty to connect directly
+    pub address: String,
+    pub username: String,
+    pub password: String,
+    // refuse any connection that doesn't go through the proxy
+    pub tor_only: bool
+}
+
+impl ProxyConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.address.trim().is_empty()
+    }
+
+    fn socks5(&self) -> Option<Socks5Config> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let address = self.address.trim();
+
+        if self.username.is_empty() {
+            Some(Socks5Config::new(address))
+        } else {
+            Some(Socks5Config::with_credentials(address, self.username.clone(), self.password.clone()))
+        }
+    }
+}
+
+// Everything needed to open a connection to one Electrum server.
+#[derive(Debug, Clone, Default, PartialEq, Hash)]
+pub struct ServerSettings {
+    pub url: String,
+    pub proxy: ProxyConfig,
+    pub tls: TlsConfig
+}
+
+impl ServerSettings {
+    pub fn new(url: &str) -> Self {
+        ServerSettings {
+            url: url.to_string(),
+            ..ServerSettings::default()
+        }
+    }
+
+    // refuses connections that would leak our IP or cannot work without a proxy
+    pub fn check(&self) -> Result<(), Error> {
+        if self.proxy.is_enabled() {
+            return Ok(());
+        }
+
+        if self.proxy.tor_only {
+            return Err(Error::Message(format!("Tor only mode refuses a clearnet connection to {}", self.url)));
+        }
+
+        if is_onion(&self.url) {
+            return Err(Error::Message(format!("{} is an onion service and needs a Tor proxy", self.url)));
+        }
+
+        Ok(())
+    }
+
+    // clients are only shared between identical settings, never between proxied and direct ones
+    fn pool_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{:?}|{}|{}",
+            self.url,
+            self.proxy.address.trim(),
+            self.proxy.username,
+            self.proxy.password,
+            self.proxy.tor_only,
+            self.tls.mode,
+            self.tls.fingerprint,
+            self.tls.certificate_path
+        )
+    }
+}
+
+impl RetryPolicy {
+    pub fn connect(&self, server: &ServerSettings) -> Result<Client, Error> {
+        server.check()?;
+
+        // the client can only validate the domain, for the other modes it talks through a relay
+        // that checks the certificate of the very session the requests go over.
+        // A first use fingerprint is persisted by whoever called `check_certificate` beforehand.
+        if server.url.starts_with("ssl://") && !server.tls.validate_domain() {
+            let relay = TlsRelay::start(server, Duration::from_secs(self.timeout_secs.into()))?;
+            let builder = ConfigBuilder::new()
+                .retry(self.retries)
+                .timeout(Some(self.timeout_secs))?;
+
+            let client = Client::from_config(&relay.url(), builder.build())?;
+            relay.attach()?;
+
+            return Ok(client);
+        }
+
+        let builder = ConfigBuilder::new()
+            .retry(self.retries)
+            .validate_domain(true);
+
+        // the client doesn't support a timeout on proxied connections
+        let builder = match server.proxy.socks5() {
+            Some(socks5) => builder.socks5(Some(socks5))?,
+            None => builder.timeout(Some(self.timeout_secs))?,
+        };
+
+        Client::from_config(&server.url, builder.build())
+    }
+
+    pub fn check_certificate(&self, server: &ServerSettings) -> Result<CertificateCheck, Error> {
+        tls::check_certificate(server, Duration::from_secs(self.timeout_secs.into()))
+    }
+}
+
+// Keeps one long-lived client per server.
+pub struct ElectrumPool {
+    policy: RetryPolicy,
+    clients: Mutex<HashMap<String, Arc<Client>>>
+}
+
+impl ElectrumPool {
+
+    pub fn new(policy: RetryPolicy) -> Self {
+        ElectrumPool {
+            policy,
+            clients: Mutex::new(HashMap::new())
+        }
+    }
+
+    // process wide pool shared by the GUI, the CLI and the daemon
+    pub fn global() -> Arc<ElectrumPool> {
+        Arc::clone(&POOL)
+    }
+
+    pub fn client(&self, server: &ServerSettings) -> Result<Arc<Client>, Error> {
+        // a cached client must not outlive a change that makes its connection unacceptable
+        server.check()?;
+
+        let key = server.pool_key();
+
+        if let Some(client) = self.clients.lock().unwrap().get(&key) {
+            return Ok(Arc::clone(client));
+        }
+
+        // connecting can take the whole timeout, don't keep the other servers waiting meanwhile
+        let client = Arc::new(self.policy.connect(server)?);
+
+        // another thread may have connected in the meantime, everyone shares the first client
+        let mut clients = self.clients.lock().unwrap();
+
+        Ok(Arc::clone(clients.entry(key).or_insert(client)))
+    }
+
+    pub fn connect(&self, server: &ServerSettings) -> Result<Client, Error> {
+        self.policy.connect(server)
+    }
+
+    pub fn check_certificate(&self, server: &ServerSettings) -> Result<CertificateCheck, Error> {
+        self.policy.check_certificate(server)
+    }
+
+    pub fn invalidate(&self, server: &ServerSettings) {
+        self.clients.lock().unwrap().remove(&server.pool_key());
+    }
+}
+
+pub struct UrlConnector {
+    server: ServerSettings,
+    pool: Arc<ElectrumPool>
+}
+
+impl UrlConnector {
+    pub fn new(server: ServerSettings) -> Self {
+        Self::with_pool(server, ElectrumPool::global())
+    }
+
+    pub fn with_pool(server: ServerSettings, pool: Arc<ElectrumPool>) -> Self {
+        UrlConnector {
+            server,
+            pool
+        }
+    }
+}
+
+impl ElectrumConnector for UrlConnector {
+    fn connect(&self) -> Result<Client, Error> {
+        self.pool.connect(&self.server)
+    }
+
+    fn client(&self) -> Result<Arc<Client>, Error> {
+        self.pool.client(&self.server)
+    }
+
+    fn invalidate(&self) {
+        self.pool.invalidate(&self.server)
+    }
+
+    fn url(&self) -> &str {
+        &self.server.url
+    }
+}
+
+// Talks to the first reachable server of the list and moves on to the next
+// one when the active server stops answering.
+pub struct FailoverConnector {
+    servers: Vec<ServerSettings>,
+    active: AtomicUsize,
+    pool: Arc<ElectrumPool>
+}
+
+impl FailoverConnector {
+    pub fn new(servers: Vec<ServerSettings>) -> Self {
+        assert!(!servers.is_empty(), "at least one server is required");
+
+        FailoverConnector {
+            servers,
+            active: AtomicUsize::new(0),
+            pool: ElectrumPool::global()
+        }
+    }
+
+    // tries every server, starting with the active one, until `f` succeeds
+    fn with_failover<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&ServerSettings) -> Result<T, Error>
+    {
+        let start = self.active.load(Ordering::SeqCst);
+        let mut errors = Vec::<(String, Error)>::new();
+
+        for n in 0..self.servers.len() {
+            let index = (start + n) % self.servers.len();
+
+            match f(&self.servers[index]) {
+                Ok(result) => {
+                    if index != start {
+                        self.active.store(index, Ordering::SeqCst);
+                    }
+                    return Ok(result);
+                },
+                Err(e) => errors.push((self.servers[index].url.clone(), e)),
+            }
+        }
+
+        // a single server keeps its own error, otherwise every reason is reported
+        if errors.len() == 1 {
+            return Err(errors.remove(0).1);
+        }
+
+        let reasons: Vec<String> = errors.iter()
+            .map(|(url, e)| format!("{}: {}", url, e))
+            .collect();
+
+        Err(Error::Message(format!("No server reachable ({})", reasons.join("; "))))
+    }
+}
+
+impl ElectrumConnector for FailoverConnector {
+    fn connect(&self) -> Result<Client, Error> {
+        self.with_failover(|server| self.pool.connect(server))
+    }
+
+    fn client(&self) -> Result<Arc<Client>, Error> {
+        self.with_failover(|server| self.pool.client(server))
+    }
+
+    // the broken server goes to the back of the queue
+    fn invalidate(&self) {
+        let active = self.active.load(Ordering::SeqCst);
+
+        self.pool.invalidate(&self.servers[active]);
+        self.active.store((active + 1) % self.servers.len(), Ordering::SeqCst);
+    }
+
+    fn close(&self) {
+        for server in &self.servers {
+            self.pool.invalidate(server);
+        }
+    }
+
+    fn secondary_client(&self) -> Option<Result<Arc<Client>, Error>> {
+        if self.servers.len() < 2 {
+            return None;
+        }
+
+        let secondary = (self.active.load(Ordering::SeqCst) + 1) % self.servers.len();
+
+        Some(self.pool.client(&self.servers[secondary]))
+    }
+
+    fn url(&self) -> &str {
+        &self.servers[self.active.load(Ordering::SeqCst)].url
+    }
+}
+
+// Asks a second server for the tip and for the history of `scripts` and
+// describes every answer that differs from the primary server.
+pub fn cross_check<P: ElectrumApi, S: ElectrumApi>(primary: &P, secondary: &S, scripts: &[Script]) -> Result<Vec<String>, Error> {
+    let mut disagreements = Vec::<String>::new();
+
+    let primary_tip = primary.block_headers_subscribe()?;
+    let secondary_tip = secondary.block_headers_subscribe()?;
+
+    // servers can be a block apart for a moment, compare the header both of them know
+    let height = primary_tip.height.min(secondary_tip.height);
+
+    if primary_tip.height.max(secondary_tip.height) - height > 1 {
+        disagreements.push(format!("Tip height differs: {} vs {}", primary_tip.height, secondary_tip.height));
+    }
+
+    let primary_header = primary.block_header(height)?;
+    let secondary_header = secondary.block_header(height)?;
+
+    if primary_header.block_hash() != secondary_header.block_hash() {
+        disagreements.push(format!("Block {} differs: {} vs {}", height, primary_header.block_hash(), secondary_header.block_hash()));
+    }
+
+    let primary_histories = primary.batch_script_get_history(scripts)?;
+    let secondary_histories = secondary.batch_script_get_history(scripts)?;
+
+    for (n, (primary_history, secondary_history)) in primary_histories.iter().zip(secondary_histories.iter()).enumerate() {
+        let primary_txs: HashSet<(Txid, i32)> = primary_history.iter().map(|h| (h.tx_hash, h.height)).collect();
+        let secondary_txs: HashSet<(Txid, i32)> = secondary_history.iter().map(|h| (h.tx_hash, h.height)).collect();
+
+        if primary_txs != secondary_txs {
+            disagreements.push(format!("History of script {} differs: {} vs {} transactions",
+                scripts[n], primary_txs.len(), secondary_txs.len()));
+        }
+    }
+
+    Ok(disagreements)
+}
+
+fn is_onion(url: &str) -> bool {
+    let host = url.split("://").last().unwrap_or(url);
+    let host = host.split(':').next().unwrap_or(host);
+
+    host.ends_with(".onion")
+}
@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use std::fmt;
+
+use crate::format::{format_timestamp, parse_date, year_of};
+use crate::gains::GainsReport;
+use crate::wallet_service::{AddressRow, HistoryRow, TransactionRow, UTXORow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Csv,
+    Json
+}
+
+impl Default for ExportFormat {
+    fn default() -> Self {
+        ExportFormat::Csv
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+pub static EXPORT_FORMATS: [ExportFormat; 2] = [ExportFormat::Csv, ExportFormat::Json];
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+// Days of the history to export, both ends included and open when None.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DateRange {
+    pub from: Option<u64>,
+    // first second after the range
+    pub until: Option<u64>
+}
+
+impl DateRange {
+    // YYYY-MM-DD dates, empty for an open end
+    pub fn parse(from: &str, to: &str) -> Result<Self, String> {
+        let from = match from.trim() {
+            "" => None,
+            date => Some(parse_date(date)?),
+        };
+
+        let until = match to.trim() {
+            "" => None,
+            date => Some(parse_date(date)? + 86_400),
+        };
+
+        if let (Some(from), Some(until)) = (from, until) {
+            if until <= from {
+                return Err("The end of the date range is before its start".to_string());
+            }
+        }
+
+        Ok(DateRange {
+            from,
+            until
+        })
+    }
+
+    // unconfirmed transactions (no timestamp) belong to a range without an end
+    pub fn contains(&self, timestamp: Option<u64>) -> bool {
+        match timestamp {
+            Some(timestamp) => self.from.map_or(true, |from| timestamp >= from) && self.until.map_or(true, |until| timestamp < until),
+            None => self.until.is_none(),
+        }
+    }
+}
+
+// quotes a CSV field when it contains a separator, a quote or a line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    fields.iter().map(|field| csv_field(field)).collect::<Vec<String>>().join(",")
+}
+
+pub fn addresses_csv(receiving: &[AddressRow], change: &[AddressRow]) -> String {
+    let mut lines = vec![csv_line(&["index".into(), "type".into(), "address".into(), "balance".into(), "tx_count".into()])];
+
+    let rows = receiving.iter().map(|row| (row, "receiving"))
+        .chain(change.iter().map(|row| (row, "change")));
+
+    for (row, keychain) in rows {
+        lines.push(csv_line(&[row.index.to_string(), keychain.to_string(), row.address.clone(), row.balance.to_string(), row.tx_count.to_string()]));
+    }
+
+    lines.join("\n")
+}
+
+pub fn utxos_csv(rows: &[UTXORow]) -> String {
+    let mut lines = vec![csv_line(&["outpoint".into(), "address".into(), "amount".into(), "height".into()])];
+
+    for row in rows {
+        lines.push(csv_line(&[format!("{}:{}", row.txid, row.vout), row.address.clone(), row.amount.to_string(), row.height.to_string()]));
+    }
+
+    lines.join("\n")
+}
+
+pub fn transactions_csv(rows: &[TransactionRow]) -> String {
+    let mut lines = vec![csv_line(&["txid".into(), "amount".into(), "fee".into(), "height".into()])];
+
+    for row in rows {
+        lines.push(csv_line(&[
+            row.txid.clone(),
+            row.amount.to_string(),
+            row.fee.map(|fee| fee.to_string()).unwrap_or_default(),
+            row.height.to_string()
+        ]));
+    }
+
+    lines.join("\n")
+}
+
+pub fn addresses_json(receiving: &[AddressRow], change: &[AddressRow]) -> String {
+    let rows: Vec<_> = receiving.iter().map(|row| (row, "receiving"))
+        .chain(change.iter().map(|row| (row, "change")))
+        .map(|(row, keychain)| json!({
+            "index": row.index,
+            "type": keychain,
+            "address": row.address,
+            "balance": row.balance,
+            "tx_count": row.tx_count
+        }))
+        .collect();
+
+    serde_json::to_string_pretty(&rows).expect("rows are always serializable")
+}
+
+pub fn utxos_json(rows: &[UTXORow]) -> String {
+    let rows: Vec<_> = rows.iter().map(|row| json!({
+        "outpoint": format!("{}:{}", row.txid, row.vout),
+        "address": row.address,
+        "amount": row.amount,
+        "height": row.height
+    })).collect();
+
+    serde_json::to_string_pretty(&rows).expect("rows are always serializable")
+}
+
+// labels and counterparties are joined with "; " to stay in one column
+pub fn history_csv(rows: &[HistoryRow], range: &DateRange) -> String {
+    let mut lines = vec![csv_line(&[
+        "txid".into(), "date".into(), "height".into(), "confirmations".into(), "net_amount".into(),
+        "fee".into(), "labels".into(), "counterparties".into()
+    ])];
+
+    for row in rows.iter().filter(|row| range.contains(row.timestamp)) {
+        lines.push(csv_line(&[
+            row.txid.clone(),
+            row.timestamp.map(format_timestamp).unwrap_or_default(),
+            row.height.to_string(),
+            row.confirmations.to_string(),
+            row.amount.to_string(),
+            row.fee.map(|fee| fee.to_string()).unwrap_or_default(),
+            row.labels.join("; "),
+            row.counterparties.join("; ")
+        ]));
+    }
+
+    lines.join("\n")
+}
+
+pub fn history_json(rows: &[HistoryRow], range: &DateRange) -> String {
+    let rows: Vec<_> = rows.iter()
+        .filter(|row| range.contains(row.timestamp))
+        .map(|row| json!({
+            "txid": row.txid,
+            "date": row.timestamp.map(format_timestamp),
+            "timestamp": row.timestamp,
+            "height": row.height,
+            "confirmations": row.confirmations,
+            "net_amount": row.amount as i64,
+            "fee": row.fee,
+            "labels": row.labels,
+            "counterparties": row.counterparties
+        }))
+        .collect();
+
+    serde_json::to_string_pretty(&rows).expect("rows are always serializable")
+}
+
+pub fn export_history(rows: &[HistoryRow], range: &DateRange, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => history_csv(rows, range),
+        ExportFormat::Json => history_json(rows, range),
+    }
+}
+
+pub fn export_addresses(receiving: &[AddressRow], change: &[AddressRow], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => addresses_csv(receiving, change),
+        ExportFormat::Json => addresses_json(receiving, change),
+    }
+}
+
+pub fn export_utxos(rows: &[UTXORow], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => utxos_csv(rows),
+        ExportFormat::Json => utxos_json(rows),
+    }
+}
+
+// one line per disposed part of a lot, then the transfers between our wallets with only their fee; fiat values have two decimals
+pub fn gains_csv(report: &GainsReport) -> String {
+    let mut lines = vec![csv_line(&[
+        "type".into(), "year".into(), "txid".into(), "date".into(), "sats".into(), "fee".into(), "lot_txid".into(),
+        "acquired".into(), "cost_basis".into(), "proceeds".into(), "gain".into()
+    ])];
+
+    for disposal in &report.disposals {
+        lines.push(csv_line(&[
+            "disposal".into(),
+            year_of(disposal.disposed).to_string(),
+            disposal.txid.clone(),
+            format_timestamp(disposal.disposed),
+            disposal.sats.to_string(),
+            String::new(),
+            disposal.lot_txid.clone().unwrap_or_default(),
+            disposal.acquired.map(format_timestamp).unwrap_or_default(),
+            format!("{:.2}", disposal.cost_basis),
+            format!("{:.2}", disposal.proceeds),
+            format!("{:.2}", disposal.gain())
+        ]));
+    }
+
+    for transfer in &report.transfers {
+        lines.push(csv_line(&[
+            "transfer".into(),
+            year_of(transfer.timestamp).to_string(),
+            transfer.txid.clone(),
+            format_timestamp(transfer.timestamp),
+            String::new(),
+            transfer.fee.to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new()
+        ]));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2021-01-01 and 2021-01-31 at midnight UTC
+    const JAN_1: u64 = 1_609_459_200;
+    const JAN_31: u64 = 1_612_051_200;
+
+    fn row(txid: &str, timestamp: Option<u64>) -> HistoryRow {
+        HistoryRow {
+            txid: txid.to_string(),
+            timestamp,
+            amount: 1000,
+            ..HistoryRow::default()
+        }
+    }
+
+    #[test]
+    fn end_day_is_included() {
+        let range = DateRange::parse("2021-01-01", "2021-01-31").unwrap();
+
+        assert_eq!(range, DateRange { from: Some(JAN_1), until: Some(JAN_31 + 86_400) });
+
+        assert!(!range.contains(Some(JAN_1 - 1)));
+        assert!(range.contains(Some(JAN_1)));
+        assert!(range.contains(Some(JAN_31 + 86_399)));
+        assert!(!range.contains(Some(JAN_31 + 86_400)));
+    }
+
+    #[test]
+    fn a_single_day_is_a_valid_range() {
+        let range = DateRange::parse(" 2021-01-31 ", "2021-01-31").unwrap();
+
+        assert!(range.contains(Some(JAN_31 + 12 * 3600)));
+        assert!(!range.contains(Some(JAN_1)));
+    }
+
+    #[test]
+    fn end_before_start_is_an_error() {
+        assert_eq!(DateRange::parse("2021-01-31", "2021-01-01"), Err("The end of the date range is before its start".to_string()));
+        assert!(DateRange::parse("2021-01-31", "31/01/2021").is_err());
+    }
+
+    #[test]
+    fn unconfirmed_rows_only_belong_to_an_open_ended_range() {
+        assert!(DateRange::default().contains(None));
+        assert!(DateRange::parse("2021-01-01", "").unwrap().contains(None));
+        assert!(!DateRange::parse("", "2021-01-31").unwrap().contains(None));
+        assert!(DateRange::parse("", "2021-01-31").unwrap().contains(Some(0)));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("Receive #3"), "Receive #3");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn history_csv_keeps_the_rows_in_range() {
+        let rows = [row("old", Some(JAN_1 - 1)), row("in", Some(JAN_31)), row("pending", None)];
+
+        let bounded = history_csv(&rows, &DateRange::parse("2021-01-01", "2021-01-31").unwrap());
+        let open = history_csv(&rows, &DateRange::parse("2021-01-01", "").unwrap());
+
+        let txids = |csv: &str| csv.lines().skip(1).map(|line| line.split(',').next().unwrap().to_string()).collect::<Vec<String>>();
+
+        assert_eq!(txids(&bounded), vec!["in"]);
+        assert_eq!(txids(&open), vec!["in", "pending"]);
+    }
+}
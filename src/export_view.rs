@@ -0,0 +1,157 @@
+use iced::{button, pick_list, text_input, Align, Button, Column, Element, HorizontalAlignment, Length, PickList, Row, Text, TextInput};
+
+use rwallet::export::{ExportFormat, EXPORT_FORMATS};
+use crate::theme::{ButtonStyle, InputStyle, Palette, PickListStyle};
+use crate::{RuWalletMessage, Table, ROBOTO, ROBOTO_BOLD};
+
+// Writes the wallet lists to CSV or JSON files, the transactions within a date range.
+#[derive(Debug)]
+pub struct ExportView {
+    format_pick_list_state: pick_list::State<ExportFormat>,
+    pub format: ExportFormat,
+
+    from_input_state: text_input::State,
+    pub from_input_value: String,
+
+    to_input_state: text_input::State,
+    pub to_input_value: String,
+
+    directory_input_state: text_input::State,
+    pub directory_input_value: String,
+
+    transactions_button_state: button::State,
+    addresses_button_state: button::State,
+    utxos_button_state: button::State,
+
+    // path of the last export or why it failed
+    pub status: String
+}
+
+impl Default for ExportView {
+    fn default() -> Self {
+        let directory = dirs_next::document_dir()
+            .or_else(dirs_next::home_dir)
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| ".".to_string());
+
+        ExportView {
+            format_pick_list_state: pick_list::State::default(),
+            format: ExportFormat::default(),
+            from_input_state: text_input::State::default(),
+            from_input_value: String::new(),
+            to_input_state: text_input::State::default(),
+            to_input_value: String::new(),
+            directory_input_state: text_input::State::default(),
+            directory_input_value: directory,
+            transactions_button_state: button::State::default(),
+            addresses_button_state: button::State::default(),
+            utxos_button_state: button::State::default(),
+            status: String::new()
+        }
+    }
+}
+
+impl ExportView {
+
+    pub fn view(&mut self, palette: Palette) -> Element<RuWalletMessage> {
+
+        let export_title = Text::new("Export")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let format_pick_list = PickList::new(
+            &mut self.format_pick_list_state,
+            &EXPORT_FORMATS[..],
+            Some(self.format),
+            RuWalletMessage::ExportFormatSelected
+        )
+        .padding(15)
+        .text_size(20)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        let from_input = TextInput::new(
+            &mut self.from_input_state,
+            "From (YYYY-MM-DD)",
+            &mut self.from_input_value,
+            RuWalletMessage::ExportFromInputChanged
+        )
+        .width(Length::Units(220))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let to_input = TextInput::new(
+            &mut self.to_input_state,
+            "To (YYYY-MM-DD)",
+            &mut self.to_input_value,
+            RuWalletMessage::ExportToInputChanged
+        )
+        .width(Length::Units(220))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let directory_input = TextInput::new(
+            &mut self.directory_input_state,
+            "Export Directory",
+            &mut self.directory_input_value,
+            RuWalletMessage::ExportDirectoryInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let export_buttons = Row::new()
+            .spacing(20)
+            .push(
+                Button::new(&mut self.transactions_button_state, Text::new("Export Transactions"))
+                    .padding(15)
+                    .style(ButtonStyle(palette))
+                    .on_press(RuWalletMessage::ExportList(Table::Transactions))
+            )
+            .push(
+                Button::new(&mut self.addresses_button_state, Text::new("Export Addresses"))
+                    .padding(15)
+                    .style(ButtonStyle(palette))
+                    .on_press(RuWalletMessage::ExportList(Table::Addresses))
+            )
+            .push(
+                Button::new(&mut self.utxos_button_state, Text::new("Export Unspent"))
+                    .padding(15)
+                    .style(ButtonStyle(palette))
+                    .on_press(RuWalletMessage::ExportList(Table::Utxos))
+            );
+
+        let mut export = Column::new()
+            .spacing(20)
+            .push(export_title)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(format_pick_list)
+                    .push(from_input)
+                    .push(to_input)
+                    .push(directory_input)
+            )
+            .push(export_buttons);
+
+        if !self.status.is_empty() {
+            export = export.push(
+                Text::new(&self.status)
+                    .font(ROBOTO)
+                    .width(Length::Fill)
+                    .size(20)
+            );
+        }
+
+        export.into()
+    }
+}
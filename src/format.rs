@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+// "YYYY-MM-DD HH:MM" in UTC for a unix timestamp in seconds
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86_400) as i64);
+    let seconds = timestamp % 86_400;
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, seconds / 3600, seconds % 3600 / 60)
+}
+
+// calendar year, in UTC, of a unix timestamp in seconds
+pub fn year_of(timestamp: u64) -> i64 {
+    civil_from_days((timestamp / 86_400) as i64).0
+}
+
+// days since 1970-01-01 to (year, month, day), see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+// "YYYY-MM-DD" to the unix timestamp of midnight UTC
+pub fn parse_date(value: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid date '{}', expected YYYY-MM-DD", value);
+
+    let parts: Vec<&str> = value.trim().split('-').collect();
+
+    if parts.len() != 3 {
+        return Err(invalid());
+    }
+
+    let year: i64 = parts[0].parse().map_err(|_| invalid())?;
+    let month: u32 = parts[1].parse().map_err(|_| invalid())?;
+    let day: u32 = parts[2].parse().map_err(|_| invalid())?;
+
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    let days = days_from_civil(year, month, day);
+
+    // a day past the end of the month lands in the next one, e.g. 2021-02-31 on March 3rd
+    if civil_from_days(days) != (year, month, day) {
+        return Err(invalid());
+    }
+
+    Ok(days as u64 * 86_400)
+}
+
+// inverse of `civil_from_days`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+pub const SATS_PER_BTC: u64 = 100_000_000;
+
+// Unit amounts are shown and typed in, chosen once for the whole application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BitcoinUnit {
+    Btc,
+    MilliBtc,
+    Bits,
+    Sats
+}
+
+impl Default for BitcoinUnit {
+    fn default() -> Self {
+        BitcoinUnit::Sats
+    }
+}
+
+impl fmt::Display for BitcoinUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitcoinUnit::Btc => write!(f, "BTC"),
+            BitcoinUnit::MilliBtc => write!(f, "mBTC"),
+            BitcoinUnit::Bits => write!(f, "bits"),
+            BitcoinUnit::Sats => write!(f, "sats"),
+        }
+    }
+}
+
+pub static BITCOIN_UNITS: [BitcoinUnit; 4] = [BitcoinUnit::Btc, BitcoinUnit::MilliBtc, BitcoinUnit::Bits, BitcoinUnit::Sats];
+
+impl BitcoinUnit {
+
+    // digits after the decimal point, always shown so amounts line up
+    pub fn decimals(self) -> usize {
+        match self {
+            BitcoinUnit::Btc => 8,
+            BitcoinUnit::MilliBtc => 5,
+            BitcoinUnit::Bits => 2,
+            BitcoinUnit::Sats => 0,
+        }
+    }
+
+    fn sats_per_unit(self) -> u64 {
+        10u64.pow(self.decimals() as u32)
+    }
+
+    // e.g. "1,234.56780000" in BTC or "123,456,780" in sats
+    pub fn format(self, sats: u64) -> String {
+        let whole = group_thousands(sats / self.sats_per_unit());
+
+        if self.decimals() == 0 {
+            return whole;
+        }
+
+        format!("{}.{:0width$}", whole, sats % self.sats_per_unit(), width = self.decimals())
+    }
+
+    // explicit sign for amounts entering (+) or leaving (-) the wallet
+    pub fn format_signed(self, sats: i128) -> String {
+        let amount = self.format(sats.unsigned_abs() as u64);
+
+        match sats.signum() {
+            1 => format!("+{}", amount),
+            -1 => format!("-{}", amount),
+            _ => amount,
+        }
+    }
+
+    // same as `format` with the unit appended
+    pub fn format_with_unit(self, sats: u64) -> String {
+        format!("{} {}", self.format(sats), self)
+    }
+
+    // parses an amount typed in this unit into sats, thousands separators are ignored
+    pub fn parse(self, value: &str) -> Result<u64, String> {
+        let value = value.trim();
+        let invalid = || format!("Invalid amount '{}'", value);
+        let decimals = self.decimals();
+
+        let digits = value.replace(',', "");
+
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (digits.as_str(), ""),
+        };
+
+        if (whole.is_empty() && fraction.is_empty()) || fraction.len() > decimals
+            || !whole.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+        let fraction: u64 = if decimals == 0 { 0 } else { format!("{:0<width$}", fraction, width = decimals).parse().map_err(|_| invalid())? };
+
+        whole.checked_mul(self.sats_per_unit())
+            .and_then(|sats| sats.checked_add(fraction))
+            .ok_or_else(invalid)
+    }
+}
+
+// BTC with up to 8 decimals and no trailing zeros, e.g. 0.0015
+pub fn format_btc(sats: u64) -> String {
+    let whole = sats / SATS_PER_BTC;
+    let fraction = sats % SATS_PER_BTC;
+
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:08}", fraction);
+
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+// parses a BTC amount with at most 8 decimals into sats, as found in payment URIs
+pub fn parse_btc(value: &str) -> Result<u64, String> {
+    if value.contains(',') {
+        return Err(format!("Invalid amount '{}'", value.trim()));
+    }
+
+    BitcoinUnit::Btc.parse(value)
+}
+
+// fiat value with two decimals, e.g. "1,234.56 USD"
+pub fn format_fiat(value: f64, currency: &str) -> String {
+    let cents = (value.abs() * 100.0).round() as u64;
+    let sign = if value < 0.0 && cents > 0 { "-" } else { "" };
+
+    format!("{}{}.{:02} {}", sign, group_thousands(cents / 100), cents % 100, currency)
+}
+
+// same as `format_fiat` with a "+" on positive values
+pub fn format_fiat_signed(value: f64, currency: &str) -> String {
+    let formatted = format_fiat(value, currency);
+
+    if value > 0.0 && (value * 100.0).round() > 0.0 { format!("+{}", formatted) } else { formatted }
+}
+
+// 1234567 to "1,234,567"
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (n, digit) in digits.chars().enumerate() {
+        if n > 0 && (digits.len() - n) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_with_thousands_separators_and_all_decimals() {
+        assert_eq!(BitcoinUnit::Btc.format(123_456_780_000), "1,234.56780000");
+        assert_eq!(BitcoinUnit::Btc.format(1), "0.00000001");
+        assert_eq!(BitcoinUnit::MilliBtc.format(123_456_789), "1,234.56789");
+        assert_eq!(BitcoinUnit::Bits.format(1_000_050), "10,000.50");
+        assert_eq!(BitcoinUnit::Sats.format(123_456_780), "123,456,780");
+        assert_eq!(BitcoinUnit::Sats.format(999), "999");
+        assert_eq!(BitcoinUnit::Sats.format(0), "0");
+    }
+
+    #[test]
+    fn signs_follow_the_direction() {
+        assert_eq!(BitcoinUnit::Sats.format_signed(1_500), "+1,500");
+        assert_eq!(BitcoinUnit::Sats.format_signed(-1_500), "-1,500");
+        assert_eq!(BitcoinUnit::Sats.format_signed(0), "0");
+        assert_eq!(BitcoinUnit::Btc.format_signed(-1), "-0.00000001");
+        assert_eq!(BitcoinUnit::Sats.format_with_unit(2_100), "2,100 sats");
+    }
+
+    #[test]
+    fn parses_amounts_in_every_unit() {
+        assert_eq!(BitcoinUnit::Btc.parse("1,234.5678"), Ok(123_456_780_000));
+        assert_eq!(BitcoinUnit::Btc.parse(" 0.00000001 "), Ok(1));
+        assert_eq!(BitcoinUnit::Btc.parse(".5"), Ok(50_000_000));
+        assert_eq!(BitcoinUnit::MilliBtc.parse("1.5"), Ok(150_000));
+        assert_eq!(BitcoinUnit::Bits.parse("10.25"), Ok(1_025));
+        assert_eq!(BitcoinUnit::Sats.parse("1,000"), Ok(1_000));
+    }
+
+    #[test]
+    fn rejects_invalid_amounts() {
+        // more decimals than the unit has, nothing is rounded away
+        assert!(BitcoinUnit::Btc.parse("0.000000001").is_err());
+        assert!(BitcoinUnit::Bits.parse("1.005").is_err());
+        assert!(BitcoinUnit::Sats.parse("1.5").is_err());
+
+        assert!(BitcoinUnit::Btc.parse("").is_err());
+        assert!(BitcoinUnit::Btc.parse(".").is_err());
+        assert!(BitcoinUnit::Btc.parse("-1").is_err());
+        assert!(BitcoinUnit::Btc.parse("1.2.3").is_err());
+        assert!(BitcoinUnit::Btc.parse("abc").is_err());
+        assert!(BitcoinUnit::Sats.parse("18446744073709551616").is_err());
+        assert!(BitcoinUnit::Btc.parse("184467440738").is_err());
+    }
+
+    #[test]
+    fn payment_uri_amounts_have_no_separators() {
+        assert_eq!(format_btc(150_000), "0.0015");
+        assert_eq!(format_btc(2 * SATS_PER_BTC), "2");
+        assert_eq!(parse_btc("0.0015"), Ok(150_000));
+        assert!(parse_btc("1,000").is_err());
+    }
+
+    #[test]
+    fn fiat_is_rounded_to_cents() {
+        assert_eq!(format_fiat(1234.567, "USD"), "1,234.57 USD");
+        assert_eq!(format_fiat(-0.004, "EUR"), "0.00 EUR");
+        assert_eq!(format_fiat(-12.5, "EUR"), "-12.50 EUR");
+        assert_eq!(format_fiat_signed(12.5, "EUR"), "+12.50 EUR");
+        assert_eq!(format_fiat_signed(0.001, "EUR"), "0.00 EUR");
+    }
+
+    #[test]
+    fn parses_dates_to_midnight_utc() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date(" 2021-03-01 "), Ok(1_614_556_800));
+        assert_eq!(parse_date("2020-02-29"), Ok(1_582_934_400));
+        assert_eq!(format_timestamp(parse_date("2024-12-31").unwrap()), "2024-12-31 00:00");
+        assert_eq!(year_of(parse_date("2024-12-31").unwrap() + 86_399), 2024);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for date in ["2021-02-31", "2021-02-29", "2021-04-31", "2021-13-01", "2021-00-10", "2021-01-00", "1969-12-31", "2021-1", "2021/01/01", "yesterday"] {
+            assert!(parse_date(date).is_err(), "{}", date);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::format::{format_timestamp, year_of, SATS_PER_BTC};
+use crate::prices::PriceHistory;
+use crate::wallet_service::HistoryRow;
+
+// Which acquisition lots a disposal consumes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LotMethod {
+    Fifo,
+    Lifo,
+    // highest cost basis first, smallest gains
+    Hifo
+}
+
+impl Default for LotMethod {
+    fn default() -> Self {
+        LotMethod::Fifo
+    }
+}
+
+impl fmt::Display for LotMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LotMethod::Fifo => write!(f, "FIFO"),
+            LotMethod::Lifo => write!(f, "LIFO"),
+            LotMethod::Hifo => write!(f, "HIFO"),
+        }
+    }
+}
+
+pub static LOT_METHODS: [LotMethod; 3] = [LotMethod::Fifo, LotMethod::Lifo, LotMethod::Hifo];
+
+// Coins received from outside the wallets in one transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lot {
+    pub txid: String,
+    pub acquired: u64,
+    // what is left of the lot
+    pub sats: u64,
+    // fiat price of one BTC at acquisition
+    pub price: f64
+}
+
+// Part of an outgoing transaction matched against one lot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disposal {
+    pub txid: String,
+    pub disposed: u64,
+    pub sats: u64,
+    // None when the wallets hold less than what was spent, the basis is then zero
+    pub lot_txid: Option<String>,
+    pub acquired: Option<u64>,
+    pub cost_basis: f64,
+    pub proceeds: f64
+}
+
+impl Disposal {
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost_basis
+    }
+}
+
+// Transaction between our own wallets, only its fee leaves them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    pub txid: String,
+    pub timestamp: u64,
+    pub fee: u64
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YearSummary {
+    pub year: i64,
+    pub sats: u64,
+    pub proceeds: f64,
+    pub cost_basis: f64
+}
+
+impl YearSummary {
+    pub fn gain(&self) -> f64 {
+        self.proceeds - self.cost_basis
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GainsReport {
+    pub disposals: Vec<Disposal>,
+    pub transfers: Vec<Transfer>,
+    // still held at the end of the history
+    pub open_lots: Vec<Lot>
+}
+
+impl GainsReport {
+
+    // Matches every disposal of `histories` against earlier acquisitions. The
+    // histories of all our wallets are merged by txid, so coins moving between
+    // them are not a disposal. Unconfirmed transactions are left out.
+    pub fn build(histories: &[Vec<HistoryRow>], prices: &PriceHistory, method: LotMethod) -> Result<Self, String> {
+        let mut merged = BTreeMap::<String, (u64, i128, Option<u64>)>::new();
+
+        for row in histories.iter().flatten() {
+            let timestamp = match row.timestamp {
+                Some(timestamp) => timestamp,
+                None => continue,
+            };
+
+            let entry = merged.entry(row.txid.clone()).or_insert((timestamp, 0, None));
+            entry.1 += row.amount;
+            // only the paying wallet knows the fee
+            entry.2 = entry.2.or(row.fee);
+        }
+
+        let mut transactions: Vec<(String, u64, i128, Option<u64>)> = merged.into_iter()
+            .map(|(txid, (timestamp, amount, fee))| (txid, timestamp, amount, fee))
+            .collect();
+
+        transactions.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+
+        let mut report = GainsReport::default();
+
+        for (txid, timestamp, amount, fee) in transactions {
+            let price = prices.price_at(timestamp)
+                .ok_or_else(|| format!("No price before {} for transaction {}", format_timestamp(timestamp), txid))?;
+
+            if amount > 0 {
+                report.open_lots.push(Lot {
+                    txid,
+                    acquired: timestamp,
+                    sats: amount as u64,
+                    price
+                });
+                continue;
+            }
+
+            let spent = amount.unsigned_abs() as u64;
+            let fee = fee.unwrap_or(0).min(spent);
+
+            if spent == 0 {
+                continue;
+            }
+
+            // nothing but the fee left our wallets: the moved coins keep their lots and basis,
+            // the fee is disposed of for nothing, so its basis is realized as a loss instead
+            // of disappearing with the sats taken from the lots
+            let transfer = spent == fee;
+
+            // the fee lowers the proceeds of what was paid out
+            let proceeds = if transfer { 0.0 } else { (spent - fee) as f64 / SATS_PER_BTC as f64 * price };
+
+            for (lot, sats) in take_lots(&mut report.open_lots, spent, method) {
+                report.disposals.push(Disposal {
+                    txid: txid.clone(),
+                    disposed: timestamp,
+                    sats,
+                    cost_basis: lot.as_ref().map(|lot| sats as f64 / SATS_PER_BTC as f64 * lot.price).unwrap_or(0.0),
+                    proceeds: proceeds * sats as f64 / spent as f64,
+                    lot_txid: lot.as_ref().map(|lot| lot.txid.clone()),
+                    acquired: lot.map(|lot| lot.acquired)
+                });
+            }
+
+            if transfer {
+                report.transfers.push(Transfer { txid, timestamp, fee });
+            }
+        }
+
+        Ok(report)
+    }
+
+    // realized gains per calendar year of disposal, oldest year first
+    pub fn years(&self) -> Vec<YearSummary> {
+        let mut years = BTreeMap::<i64, YearSummary>::new();
+
+        for disposal in &self.disposals {
+            let year = year_of(disposal.disposed);
+            let summary = years.entry(year).or_insert(YearSummary { year, ..YearSummary::default() });
+
+            summary.sats += disposal.sats;
+            summary.proceeds += disposal.proceeds;
+            summary.cost_basis += disposal.cost_basis;
+        }
+
+        years.into_values().collect()
+    }
+}
+
+// Removes `sats` from the lots in the order of `method` and returns the parts
+// taken; a None lot covers what the lots couldn't.
+fn take_lots(lots: &mut Vec<Lot>, sats: u64, method: LotMethod) -> Vec<(Option<Lot>, u64)> {
+    match method {
+        LotMethod::Fifo => lots.sort_by_key(|lot| lot.acquired),
+        LotMethod::Lifo => lots.sort_by_key(|lot| std::cmp::Reverse(lot.acquired)),
+        LotMethod::Hifo => lots.sort_by(|a, b| b.price.partial_cmp(&a.price).unwrap_or(std::cmp::Ordering::Equal)),
+    }
+
+    let mut taken = Vec::<(Option<Lot>, u64)>::new();
+    let mut left = sats;
+
+    for lot in lots.iter_mut() {
+        if left == 0 {
+            break;
+        }
+
+        let part = left.min(lot.sats);
+        lot.sats -= part;
+        left -= part;
+
+        taken.push((Some(lot.clone()), part));
+    }
+
+    lots.retain(|lot| lot.sats > 0);
+    lots.sort_by_key(|lot| lot.acquired);
+
+    if left > 0 {
+        taken.push((None, left));
+    }
+
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BTC: u64 = SATS_PER_BTC;
+
+    // a lot of 1 BTC at 10k, 20k and 15k, then the price rises to 30k
+    fn prices() -> PriceHistory {
+        PriceHistory::from_csv("1000,10000\n2000,20000\n3000,15000\n4000,30000").unwrap()
+    }
+
+    fn row(txid: &str, timestamp: u64, amount: i128, fee: Option<u64>) -> HistoryRow {
+        HistoryRow {
+            txid: txid.to_string(),
+            timestamp: Some(timestamp),
+            amount,
+            fee,
+            ..HistoryRow::default()
+        }
+    }
+
+    fn three_lots() -> Vec<HistoryRow> {
+        vec![
+            row("a", 1000, BTC as i128, None),
+            row("b", 2000, BTC as i128, None),
+            row("c", 3000, BTC as i128, None),
+        ]
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    // half a coin spent at 30k, worth 15k
+    fn spend_half(method: LotMethod) -> GainsReport {
+        let mut history = three_lots();
+        history.push(row("spend", 4000, -(BTC as i128) / 2, Some(0)));
+
+        GainsReport::build(&[history], &prices(), method).unwrap()
+    }
+
+    fn open_lots(report: &GainsReport) -> Vec<(&str, u64)> {
+        report.open_lots.iter().map(|lot| (lot.txid.as_str(), lot.sats)).collect()
+    }
+
+    #[test]
+    fn fifo_takes_the_oldest_lot() {
+        let report = spend_half(LotMethod::Fifo);
+
+        assert_eq!(report.disposals.len(), 1);
+        assert_eq!(report.disposals[0].lot_txid.as_deref(), Some("a"));
+        assert_eq!(report.disposals[0].acquired, Some(1000));
+        assert_close(report.disposals[0].cost_basis, 5000.0);
+        assert_close(report.disposals[0].proceeds, 15000.0);
+        assert_eq!(open_lots(&report), vec![("a", BTC / 2), ("b", BTC), ("c", BTC)]);
+    }
+
+    #[test]
+    fn lifo_takes_the_newest_lot() {
+        let report = spend_half(LotMethod::Lifo);
+
+        assert_eq!(report.disposals.len(), 1);
+        assert_eq!(report.disposals[0].lot_txid.as_deref(), Some("c"));
+        assert_close(report.disposals[0].cost_basis, 7500.0);
+        assert_eq!(open_lots(&report), vec![("a", BTC), ("b", BTC), ("c", BTC / 2)]);
+    }
+
+    #[test]
+    fn hifo_takes_the_most_expensive_lot() {
+        let report = spend_half(LotMethod::Hifo);
+
+        assert_eq!(report.disposals.len(), 1);
+        assert_eq!(report.disposals[0].lot_txid.as_deref(), Some("b"));
+        assert_close(report.disposals[0].cost_basis, 10000.0);
+        assert_close(report.disposals[0].gain(), 5000.0);
+        assert_eq!(open_lots(&report), vec![("a", BTC), ("b", BTC / 2), ("c", BTC)]);
+    }
+
+    #[test]
+    fn disposal_spans_lots_and_the_fee_lowers_the_proceeds() {
+        let mut history = three_lots();
+        // 1.5 BTC leave the wallet, 0.01 of which is the fee
+        history.push(row("spend", 4000, -(BTC as i128) * 3 / 2, Some(BTC / 100)));
+
+        let report = GainsReport::build(&[history], &prices(), LotMethod::Fifo).unwrap();
+
+        let parts: Vec<(Option<&str>, u64)> = report.disposals.iter()
+            .map(|disposal| (disposal.lot_txid.as_deref(), disposal.sats))
+            .collect();
+
+        assert_eq!(parts, vec![(Some("a"), BTC), (Some("b"), BTC / 2)]);
+
+        // 1.49 BTC at 30k, shared in proportion to the sats taken from each lot
+        assert_close(report.disposals[0].proceeds, 44700.0 * 2.0 / 3.0);
+        assert_close(report.disposals[1].proceeds, 44700.0 / 3.0);
+        assert_close(report.disposals[1].cost_basis, 10000.0);
+
+        assert_eq!(open_lots(&report), vec![("b", BTC / 2), ("c", BTC)]);
+    }
+
+    #[test]
+    fn transfer_between_own_wallets_only_disposes_of_the_fee() {
+        let sender = vec![
+            row("a", 1000, BTC as i128, None),
+            row("move", 2000, -(BTC as i128) / 2 - 10_000, Some(10_000)),
+        ];
+        let receiver = vec![row("move", 2000, BTC as i128 / 2, None)];
+
+        let report = GainsReport::build(&[sender, receiver], &prices(), LotMethod::Fifo).unwrap();
+
+        // the basis of the fee, 10k sats bought at 10k, is a loss
+        assert_eq!(report.disposals.len(), 1);
+        assert_eq!(report.disposals[0].lot_txid.as_deref(), Some("a"));
+        assert_eq!(report.disposals[0].sats, 10_000);
+        assert_close(report.disposals[0].proceeds, 0.0);
+        assert_close(report.disposals[0].gain(), -1.0);
+
+        assert_eq!(report.transfers, vec![Transfer { txid: "move".to_string(), timestamp: 2000, fee: 10_000 }]);
+        assert_eq!(open_lots(&report), vec![("a", BTC - 10_000)]);
+    }
+
+    #[test]
+    fn disposal_beyond_the_lots_has_no_basis() {
+        let history = vec![
+            row("a", 1000, BTC as i128, None),
+            row("spend", 4000, -(BTC as i128) * 3 / 2, Some(0)),
+        ];
+
+        let report = GainsReport::build(&[history], &prices(), LotMethod::Fifo).unwrap();
+
+        assert_eq!(report.disposals.len(), 2);
+        assert_eq!(report.disposals[0].lot_txid.as_deref(), Some("a"));
+        assert_eq!(report.disposals[1].lot_txid, None);
+        assert_eq!(report.disposals[1].acquired, None);
+        assert_eq!(report.disposals[1].sats, BTC / 2);
+        assert_close(report.disposals[1].cost_basis, 0.0);
+        assert_close(report.disposals[1].proceeds, 15000.0);
+        assert!(report.open_lots.is_empty());
+    }
+
+    #[test]
+    fn take_lots_keeps_the_rest_in_acquisition_order() {
+        let lot = |txid: &str, acquired, price| Lot { txid: txid.to_string(), acquired, sats: 100, price };
+        let mut lots = vec![lot("a", 1, 10.0), lot("b", 2, 30.0), lot("c", 3, 20.0)];
+
+        let taken: Vec<(Option<String>, u64)> = take_lots(&mut lots, 150, LotMethod::Hifo).into_iter()
+            .map(|(lot, sats)| (lot.map(|lot| lot.txid), sats))
+            .collect();
+
+        assert_eq!(taken, vec![(Some("b".to_string()), 100), (Some("c".to_string()), 50)]);
+        assert_eq!(lots.iter().map(|lot| (lot.txid.as_str(), lot.sats)).collect::<Vec<_>>(), vec![("a", 100), ("c", 50)]);
+    }
+}
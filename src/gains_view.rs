@@ -0,0 +1,133 @@
+use iced::{button, pick_list, Align, Button, Color, Column, Element, HorizontalAlignment, Length, PickList, Row, Text};
+
+use rwallet::format::{format_fiat, format_fiat_signed, format_timestamp, BitcoinUnit};
+use rwallet::gains::{Disposal, GainsReport, LotMethod, YearSummary, LOT_METHODS};
+use crate::table::{DataTable, TableColumn};
+use crate::theme::{ButtonStyle, Palette, PickListStyle};
+use crate::{RuWalletMessage, Table, ROBOTO, ROBOTO_BOLD};
+
+// Realized gains of all our wallets, with the lots matched by the chosen method.
+#[derive(Debug, Default)]
+pub struct GainsView {
+    method_pick_list_state: pick_list::State<LotMethod>,
+    pub method: LotMethod,
+
+    build_button_state: button::State,
+    export_button_state: button::State,
+
+    pub report: Option<GainsReport>,
+
+    // path of the last export or why the report failed
+    pub status: String,
+
+    pub years_table: DataTable,
+
+    // clicking a disposal opens its transaction
+    pub disposals_table: DataTable
+}
+
+impl GainsView {
+
+    pub fn view<'a>(&'a mut self, unit: BitcoinUnit, currency: &'a str, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let gains_title = Text::new("Capital Gains")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let method_pick_list = PickList::new(
+            &mut self.method_pick_list_state,
+            &LOT_METHODS[..],
+            Some(self.method),
+            RuWalletMessage::LotMethodSelected
+        )
+        .padding(15)
+        .text_size(20)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        let build_button = Button::new(&mut self.build_button_state, Text::new("Build Report"))
+            .padding(15)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::BuildGainsReport);
+
+        let mut export_button = Button::new(&mut self.export_button_state, Text::new("Export CSV"))
+            .padding(15)
+            .style(ButtonStyle(palette));
+
+        if self.report.is_some() {
+            export_button = export_button.on_press(RuWalletMessage::ExportGainsReport);
+        }
+
+        let mut gains = Column::new()
+            .spacing(20)
+            .push(gains_title)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(method_pick_list)
+                    .push(build_button)
+                    .push(export_button)
+            );
+
+        if !self.status.is_empty() {
+            gains = gains.push(
+                Text::new(&self.status)
+                    .font(ROBOTO)
+                    .width(Length::Fill)
+                    .size(20)
+            );
+        }
+
+        let report = match &self.report {
+            Some(report) => report,
+            None => return gains.into(),
+        };
+
+        let year_columns = [
+            TableColumn::new("Year", 80, HorizontalAlignment::Left, |year: &YearSummary| year.year.to_string())
+                .sort(|a, b| a.year.cmp(&b.year)),
+            TableColumn::new(&format!("Disposed ({})", unit), 170, HorizontalAlignment::Right, move |year: &YearSummary| unit.format(year.sats)),
+            TableColumn::new("Proceeds", 190, HorizontalAlignment::Right, move |year: &YearSummary| format_fiat(year.proceeds, currency)),
+            TableColumn::new("Cost Basis", 190, HorizontalAlignment::Right, move |year: &YearSummary| format_fiat(year.cost_basis, currency)),
+            TableColumn::new("Gain", 190, HorizontalAlignment::Right, move |year: &YearSummary| format_fiat_signed(year.gain(), currency))
+                .color(move |year: &YearSummary| gain_color(year.gain(), palette)),
+        ];
+
+        let years = report.years();
+
+        let disposal_columns = [
+            TableColumn::new("Date", 160, HorizontalAlignment::Left, |disposal: &Disposal| format_timestamp(disposal.disposed))
+                .sort(|a, b| a.disposed.cmp(&b.disposed)),
+            TableColumn::new("Transaction Id", 610, HorizontalAlignment::Left, |disposal: &Disposal| disposal.txid.clone()),
+            TableColumn::new(&format!("Amount ({})", unit), 170, HorizontalAlignment::Right, move |disposal: &Disposal| unit.format(disposal.sats))
+                .sort(|a, b| a.sats.cmp(&b.sats)),
+            TableColumn::new("Acquired", 160, HorizontalAlignment::Left, |disposal: &Disposal| {
+                disposal.acquired.map(format_timestamp).unwrap_or_else(|| "unknown".to_string())
+            }),
+            TableColumn::new("Gain", 190, HorizontalAlignment::Right, move |disposal: &Disposal| format_fiat_signed(disposal.gain(), currency))
+                .color(move |disposal: &Disposal| gain_color(disposal.gain(), palette))
+                .sort(|a, b| a.gain().partial_cmp(&b.gain()).unwrap_or(std::cmp::Ordering::Equal)),
+        ];
+
+        gains
+            .push(self.years_table.view(Table::GainsYears, &year_columns, years.iter().collect(), &|year: &YearSummary| year.year.to_string(), Vec::new(), palette))
+            .push(
+                Text::new(format!("Disposals ({}), transfers between our wallets ({})", report.disposals.len(), report.transfers.len()))
+                    .font(ROBOTO_BOLD)
+                    .width(Length::Fill)
+                    .size(25)
+                    .color(palette.muted)
+            )
+            .push(self.disposals_table.view(Table::Disposals, &disposal_columns, report.disposals.iter().collect(), &|disposal: &Disposal| disposal.txid.clone(), Vec::new(), palette))
+            .into()
+    }
+}
+
+// same colours as amounts, at cent precision
+fn gain_color(gain: f64, palette: Palette) -> Color {
+    palette.amount((gain * 100.0).round() as i128)
+}
@@ -0,0 +1,17 @@
+pub mod bip21;
+pub mod config;
+pub mod electrum;
+pub mod export;
+pub mod format;
+pub mod gains;
+pub mod notifications;
+pub mod prices;
+pub mod tls;
+pub mod w_electrum;
+pub mod wallet_service;
+pub mod watcher;
+
+#[cfg(test)]
+mod mock_electrum;
+
+pub use wallet_service::{AddressDetail, AddressRow, HistoryRow, TransactionDetail, TransactionRow, UTXORow, WalletService};
@@ -1,27 +1,53 @@
-use async_std::task::block_on;
-use bdk::{Wallet, Error};
-use bdk::bitcoin::{Network, Script, Address};
-use bdk::blockchain::ElectrumBlockchain;
-use bdk::database::MemoryDatabase;
-use bdk::electrum_client::ElectrumApi;
-use bdk::wallet::{AddressIndex, AddressInfo};
-use iced::{button,text_input, Application, executor, Command, Clipboard, Element, Text, Settings, TextInput, Length, Column, Button, Scrollable, Container, scrollable, Row, Align, window, Font};
+use bdk::KeychainKind;
+use bdk::bitcoin::{Address, Network, Script};
+use iced::{button,text_input, pick_list, Application, executor, Command, Clipboard, Color, Element, Text, Settings, TextInput, Length, Column, Button, Scrollable, Container, scrollable, Row, Align, window, Font, PickList, Checkbox, Subscription};
 use iced::HorizontalAlignment;
 
-use bdk::miniscript::descriptor::DescriptorTrait;
-
-
-use bdk::descriptor::derived::AsDerived;
-
-
-
-use std::collections::HashSet;
-use std::hash::Hash;
-use std::option;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-
-mod w_electrum;
-mod test_electrum;
+use std::time::Duration;
+
+use rwallet::config::{AppConfig, Theme, WalletConfig, THEMES};
+use rwallet::export::{self, DateRange, ExportFormat};
+use rwallet::format::{format_fiat, format_timestamp, BitcoinUnit, BITCOIN_UNITS};
+use rwallet::notifications::{self, Notification, NotificationHistory, NotificationKind, TransactionChange};
+use rwallet::gains::{GainsReport, LotMethod};
+use rwallet::prices::PriceHistory;
+use rwallet::tls::{TlsMode, TLS_MODES};
+use rwallet::watcher::ChainEvent;
+use rwallet::wallet_service::refresh_addresses;
+use rwallet::{AddressDetail, AddressRow, TransactionDetail, TransactionRow, UTXORow, WalletService};
+use address_detail::AddressDetailView;
+use gains_view::GainsView;
+use export_view::ExportView;
+use notification_area::NotificationArea;
+use overview::{ChartRange, OverviewView};
+use receive::ReceiveView;
+use send::SendView;
+use table::DataTable;
+use theme::{ButtonStyle, CheckboxStyle, ContainerStyle, InputStyle, Palette, PickListStyle};
+use tables::{AddressFilter, AddressTable, TransactionFilter, TransactionTable, UtxoTable};
+use transaction_detail::TransactionDetailView;
+use wallet_list::WalletList;
+
+mod address_detail;
+mod chain_subscription;
+mod cli;
+mod daemon;
+mod export_view;
+mod gains_view;
+mod notification_area;
+mod overview;
+mod receive;
+mod send;
+mod table;
+mod tables;
+mod theme;
+mod transaction_detail;
+mod wallet_list;
+
+static NETWORKS: [Network; 4] = [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest];
 
 const ROBOTO: Font = Font::External {
     name: "RobotoMono-Regular",
@@ -35,6 +61,16 @@ const ROBOTO_BOLD: Font = Font::External {
 
 pub fn main() -> iced::Result {
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(|arg| arg == "--cli").unwrap_or(false) {
+        std::process::exit(cli::run(&args[1..]));
+    }
+
+    if args.first().map(|arg| arg == "--daemon").unwrap_or(false) {
+        std::process::exit(daemon::run(&args[1..]));
+    }
+
     RuWallet::run(Settings {
         window: window::Settings {
             size: (1600, 768),
@@ -45,32 +81,60 @@ pub fn main() -> iced::Result {
 }
 
 #[derive(Debug, Default)]
-struct AddressRow {
-    index: u64,
-    address: String,
-    balance: u64,
-    tx_count: u64
-}
+struct RuWallet{
+    config: AppConfig,
 
-#[derive(Debug, Default)]
-struct UTXORow {
-    txid: String,
-    vout: u32,
-    address: String,
-    amount: u64,
-    height: u32
-}
+    scroll: scrollable::State,
 
-#[derive(Debug, Default, Clone)]
-struct TransactionRow {
-    txid: String,
-    amount: i128,
-    height: u32
-}
+    wallet_list: WalletList,
 
-#[derive(Debug, Default)]
-struct RuWallet{
-    scroll: scrollable::State,
+    notification_area: NotificationArea,
+
+    // announced since the user last dismissed them
+    toasts: Vec<Notification>,
+
+    notification_history: NotificationHistory,
+
+    tip_height: u32,
+
+    network_pick_list_state: pick_list::State<Network>,
+
+    unit_pick_list_state: pick_list::State<BitcoinUnit>,
+
+    theme_pick_list_state: pick_list::State<Theme>,
+
+    currency_input_state: text_input::State,
+    currency_input_value: String,
+
+    price_file_input_state: text_input::State,
+    price_file_input_value: String,
+
+    load_prices_button_state: button::State,
+
+    // None until a price file is loaded, fiat values are hidden meanwhile
+    price_history: Option<PriceHistory>,
+
+    electrum_url_input_state: text_input::State,
+    electrum_url_input_value: String,
+
+    fallback_urls_input_state: text_input::State,
+    fallback_urls_input_value: String,
+
+    proxy_address_input_state: text_input::State,
+    proxy_address_input_value: String,
+
+    proxy_username_input_state: text_input::State,
+    proxy_username_input_value: String,
+
+    proxy_password_input_state: text_input::State,
+    proxy_password_input_value: String,
+
+    tls_mode_pick_list_state: pick_list::State<TlsMode>,
+
+    certificate_path_input_state: text_input::State,
+    certificate_path_input_value: String,
+
+    forget_fingerprint_button_state: button::State,
 
     external_descriptor_input_state: text_input::State,
     external_descriptor_input_value: String,
@@ -80,22 +144,155 @@ struct RuWallet{
 
     create_wallet_button_state: button::State,
 
+    wallet_service: Option<WalletService>,
+
+    // scripts subscribed to for live updates, empty until the wallet is restored
+    watched_scripts: Vec<Script>,
+
+    error_message: String,
+
+    // disagreements between the servers found in paranoid mode
+    server_warnings: Vec<String>,
+
     new_address: String,
 
+    receive_view: ReceiveView,
+
+    send_view: SendView,
+
     address_items: Vec<AddressRow>,
 
     internal_address_items: Vec<AddressRow>,
 
+    address_table: AddressTable,
+
+    // what was last copied, shown for a moment
+    copied: Option<String>,
+
+    // bumped on every copy so an older timer doesn't hide a newer indicator
+    copied_generation: u64,
+
+    address_detail: Option<AddressDetail>,
+
+    address_detail_view: AddressDetailView,
+
     utxo_items: Vec<UTXORow>,
 
-    transaction_items: Vec<TransactionRow>
+    utxo_table: UtxoTable,
+
+    transaction_items: Vec<TransactionRow>,
+
+    transaction_table: TransactionTable,
+
+    // shown instead of the tables while set
+    transaction_detail: Option<TransactionDetail>,
+
+    transaction_detail_view: TransactionDetailView,
+
+    export_view: ExportView,
+
+    gains_view: GainsView,
+
+    tab: Tab,
+
+    wallet_tab_button_state: button::State,
+
+    overview_tab_button_state: button::State,
+
+    overview_view: OverviewView
 }
 
 #[derive(Debug, Clone)]
-enum RuWalletMessage {
+pub enum RuWalletMessage {
     ExternalDescriptorInputChanged(String),
     InternalDescriptorInputChanged(String),
     CreateWallet,
+    WalletNameInputChanged(String),
+    NewWallet,
+    RenameWallet,
+    DeleteWallet,
+    ConfirmDeleteWallet,
+    CancelDeleteWallet,
+    SelectWallet(u32),
+    NetworkSelected(Network),
+    ElectrumUrlInputChanged(String),
+    FallbackUrlsInputChanged(String),
+    ParanoidToggled(bool),
+    ProxyAddressInputChanged(String),
+    ProxyUsernameInputChanged(String),
+    ProxyPasswordInputChanged(String),
+    TorOnlyToggled(bool),
+    TlsModeSelected(TlsMode),
+    CertificatePathInputChanged(String),
+    ForgetFingerprint,
+    ChainEvent(ChainEvent),
+    // wallet id and the refreshed rows of the changed addresses
+    ScriptsSynced(u32, Result<Vec<AddressRow>, String>),
+    DismissNotifications,
+    ToggleNotificationHistory,
+    ClearNotificationHistory,
+    NotifyIncomingToggled(bool),
+    NotificationThresholdsInputChanged(String),
+    SaveNotificationSettings,
+    ReceiveAmountInputChanged(String),
+    ReceiveLabelInputChanged(String),
+    ReceiveMessageInputChanged(String),
+    ZoomQrCode(bool),
+    SendRecipientInputChanged(String),
+    SendAmountInputChanged(String),
+    SendFeeRateInputChanged(String),
+    Send,
+    CloseDetail,
+    Copy(String),
+    CopyTable(Table),
+    HideCopied(u64),
+    SortTable(Table, usize),
+    SearchTable(Table, String),
+    AddressFilterSelected(AddressFilter),
+    TransactionFilterSelected(TransactionFilter),
+    ChangePage(Table, usize),
+    PageInputChanged(Table, String),
+    JumpToPage(Table),
+    SelectRow(Table, String),
+    UnitSelected(BitcoinUnit),
+    ThemeSelected(Theme),
+    CurrencyInputChanged(String),
+    PriceFileInputChanged(String),
+    LoadPrices,
+    ExportFormatSelected(ExportFormat),
+    ExportFromInputChanged(String),
+    ExportToInputChanged(String),
+    ExportDirectoryInputChanged(String),
+    ExportList(Table),
+    LotMethodSelected(LotMethod),
+    BuildGainsReport,
+    ExportGainsReport,
+    SelectTab(Tab),
+    ChartRangeSelected(ChartRange),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Wallet,
+    Overview,
+}
+
+impl Default for Tab {
+    fn default() -> Self {
+        Tab::Wallet
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Table {
+    Addresses,
+    Utxos,
+    Transactions,
+    AddressHistory,
+    TransactionInputs,
+    TransactionOutputs,
+    GainsYears,
+    Disposals,
 }
 
 impl Application for RuWallet {
@@ -104,14 +301,49 @@ impl Application for RuWallet {
     type Flags = ();
 
     fn new(_flags: ()) -> (RuWallet, Command<Self::Message>) {
-        (Self::default(), Command::none())
+        let mut ru_wallet = Self::default();
+
+        // shown once the selected wallet is loaded, which clears the error message
+        let config_error = match AppConfig::load() {
+            Ok(config) => {
+                ru_wallet.config = config;
+                None
+            },
+            Err(e) => Some(format!("{}, starting from scratch", e)),
+        };
+
+        let history_error = match NotificationHistory::load() {
+            Ok(history) => {
+                ru_wallet.notification_history = history;
+                None
+            },
+            Err(e) => Some(format!("{}, starting from scratch", e)),
+        };
+
+        ru_wallet.notification_area.thresholds_input_value = format_thresholds(&ru_wallet.config.notifications.confirmation_thresholds);
+        ru_wallet.currency_input_value = ru_wallet.config.fiat.currency.clone();
+        ru_wallet.price_file_input_value = ru_wallet.config.fiat.price_file.clone();
+        ru_wallet.load_prices();
+        ru_wallet.load_selected_wallet();
+
+        let load_errors: Vec<String> = config_error.into_iter().chain(history_error).collect();
+
+        if !load_errors.is_empty() {
+            ru_wallet.error_message = load_errors.join("\n");
+        }
+
+        (ru_wallet, Command::none())
     }
 
     fn title(&self) -> String {
         String::from("Rust Wallet")
     }
 
-    fn update(&mut self, _message: Self::Message, _clipboard: &mut Clipboard) -> Command<Self::Message> {
+    fn background_color(&self) -> Color {
+        theme::palette(self.config.theme).background
+    }
+
+    fn update(&mut self, _message: Self::Message, clipboard: &mut Clipboard) -> Command<Self::Message> {
         match _message {
             RuWalletMessage::ExternalDescriptorInputChanged(value) => {
                 self.external_descriptor_input_value = value.clone();
@@ -119,81 +351,370 @@ impl Application for RuWallet {
             RuWalletMessage::InternalDescriptorInputChanged(value) => {
                 self.internal_descriptor_input_value = value.clone();
             },
-            RuWalletMessage::CreateWallet => {
-                println!("{}", "Restoring wallet ...");
-
-                self.address_items.clear();
-                self.new_address.clear();
-                self.internal_address_items.clear();
-                self.utxo_items.clear();
-                self.transaction_items.clear();
-
-                let wallet = block_on(self.generate_wallet());
-
-                self.address_items = block_on(self.get_external_addresses(&wallet));
+            RuWalletMessage::WalletNameInputChanged(value) => {
+                self.wallet_list.name_input_value = value;
+            },
+            RuWalletMessage::NewWallet => {
+                let name = self.wallet_list.name_input_value.trim();
 
-                self.internal_address_items = block_on(self.get_internal_addresses(&wallet));
+                if !name.is_empty() {
+                    self.config.create_wallet(name);
+                    self.load_selected_wallet();
+                    self.save_config();
+                }
+            },
+            RuWalletMessage::RenameWallet => {
+                let name = self.wallet_list.name_input_value.trim().to_string();
 
-                self.new_address = wallet.get_address(AddressIndex::New).unwrap().address.to_string();
+                if let (Some(id), false) = (self.config.selected, name.is_empty()) {
+                    self.config.rename_wallet(id, &name);
+                    self.save_config();
+                }
+            },
+            // the descriptors may be the only copy of the keys, so ask first
+            RuWalletMessage::DeleteWallet => {
+                self.wallet_list.confirm_delete = true;
+            },
+            RuWalletMessage::CancelDeleteWallet => {
+                self.wallet_list.confirm_delete = false;
+            },
+            RuWalletMessage::ConfirmDeleteWallet => {
+                self.wallet_list.confirm_delete = false;
 
-                let mut tx_list = wallet.list_transactions(true).unwrap();
+                if let Some(id) = self.config.selected {
+                    // close the open database before removing it
+                    self.wallet_service = None;
 
-                tx_list.sort_by(|a, b|
-                    b.confirmation_time.as_ref().unwrap().height.cmp(&a.confirmation_time.as_ref().unwrap().height));
+                    let result = self.config.delete_wallet(id);
 
-                for tx in tx_list.iter() {
+                    self.load_selected_wallet();
+                    self.save_config();
 
-                    let height = tx.confirmation_time.as_ref().unwrap().height;
+                    if let Err(e) = result {
+                        self.error_message = format!("Cannot delete the wallet: {}", e);
+                    }
+                }
+            },
+            RuWalletMessage::SelectWallet(id) => {
+                self.wallet_list.confirm_delete = false;
+                self.config.selected = Some(id);
+                self.load_selected_wallet();
+                self.save_config();
+            },
+            RuWalletMessage::NetworkSelected(network) => {
+                if let Some(wallet_config) = self.config.selected_wallet_mut() {
+                    wallet_config.network = network;
+                }
+                self.save_config();
+                self.close_stale_wallet();
+            },
+            RuWalletMessage::ElectrumUrlInputChanged(value) => {
+                self.electrum_url_input_value = value;
+            },
+            RuWalletMessage::FallbackUrlsInputChanged(value) => {
+                self.fallback_urls_input_value = value;
+            },
+            RuWalletMessage::ParanoidToggled(paranoid) => {
+                if let Some(wallet_config) = self.config.selected_wallet_mut() {
+                    wallet_config.paranoid = paranoid;
+                }
+                self.save_config();
+                self.close_stale_wallet();
+            },
+            RuWalletMessage::ProxyAddressInputChanged(value) => {
+                self.proxy_address_input_value = value;
+            },
+            RuWalletMessage::ProxyUsernameInputChanged(value) => {
+                self.proxy_username_input_value = value;
+            },
+            RuWalletMessage::ProxyPasswordInputChanged(value) => {
+                self.proxy_password_input_value = value;
+            },
+            RuWalletMessage::TorOnlyToggled(tor_only) => {
+                if let Some(wallet_config) = self.config.selected_wallet_mut() {
+                    wallet_config.proxy.tor_only = tor_only;
+                }
+                self.save_config();
+                self.close_stale_wallet();
+            },
+            RuWalletMessage::CreateWallet => {
 
-                    let amount = tx.received as i128 - tx.sent as i128;
+                self.clear_wallet_items();
+                self.error_message.clear();
+                self.server_warnings.clear();
 
-                    self.transaction_items.push(
-                        TransactionRow {
-                            txid: tx.txid.to_string(),
-                            amount,
-                            height
-                        }
-                    );
+                if let Err(e) = self.restore_wallet() {
+                    self.error_message = e.to_string();
+                }
+            },
+            RuWalletMessage::TlsModeSelected(mode) => {
+                if let Some(wallet_config) = self.config.selected_wallet_mut() {
+                    if wallet_config.tls.mode != mode {
+                        wallet_config.tls.mode = mode;
+                        wallet_config.tls.fingerprint.clear();
+                    }
+                }
+                self.save_config();
+                self.close_stale_wallet();
+            },
+            RuWalletMessage::CertificatePathInputChanged(value) => {
+                self.certificate_path_input_value = value;
+            },
+            RuWalletMessage::ForgetFingerprint => {
+                if let Some(wallet_config) = self.config.selected_wallet_mut() {
+                    wallet_config.tls.fingerprint.clear();
+                }
+                self.save_config();
+                self.close_stale_wallet();
+            },
+            RuWalletMessage::ChainEvent(event) => {
+                match self.handle_chain_event(event) {
+                    Ok(command) => return command,
+                    Err(e) => self.error_message = e.to_string(),
+                }
+            },
+            RuWalletMessage::ScriptsSynced(wallet_id, result) => {
+                if let Err(e) = result.map_err(bdk::Error::Generic).and_then(|changed| self.finish_script_sync(wallet_id, &changed)) {
+                    self.error_message = e.to_string();
+                }
+            },
+            // rows of the wallet lists open their detail view
+            RuWalletMessage::SelectRow(table, key) => {
+                self.data_table(table).select(&key);
+
+                match table {
+                    Table::Addresses => self.show_address(&key),
+                    Table::Transactions | Table::AddressHistory | Table::Disposals => self.show_transaction(&key),
+                    Table::Utxos | Table::TransactionInputs | Table::TransactionOutputs | Table::GainsYears => {},
+                }
+            },
+            // a transaction opened from an address goes back to that address
+            RuWalletMessage::CloseDetail => {
+                if self.transaction_detail.is_some() {
+                    self.transaction_detail = None;
+                } else {
+                    self.address_detail = None;
                 }
+            },
+            RuWalletMessage::ReceiveAmountInputChanged(value) => {
+                self.receive_view.amount_input_value = value;
+                self.receive_view.update(&self.new_address, self.config.unit);
+            },
+            RuWalletMessage::ReceiveLabelInputChanged(value) => {
+                self.receive_view.label_input_value = value;
+                self.receive_view.update(&self.new_address, self.config.unit);
+            },
+            RuWalletMessage::ReceiveMessageInputChanged(value) => {
+                self.receive_view.message_input_value = value;
+                self.receive_view.update(&self.new_address, self.config.unit);
+            },
+            RuWalletMessage::ZoomQrCode(zoom_in) => {
+                self.receive_view.zoom(zoom_in);
+            },
+            RuWalletMessage::SendRecipientInputChanged(value) => {
+                let network = self.config.selected_wallet().map(|w| w.network).unwrap_or(Network::Testnet);
+                self.send_view.recipient_changed(value, network, self.config.unit);
+            },
+            RuWalletMessage::SendAmountInputChanged(value) => {
+                self.send_view.amount_input_value = value;
+            },
+            RuWalletMessage::SendFeeRateInputChanged(value) => {
+                self.send_view.fee_rate_input_value = value;
+            },
+            RuWalletMessage::Send => {
+                if let Err(e) = self.send() {
+                    self.send_view.status = e;
+                }
+            },
+            RuWalletMessage::Copy(value) => {
+                clipboard.write(value.clone());
+                return self.show_copied(value);
+            },
+            RuWalletMessage::CopyTable(table) => {
+                let (name, csv) = match table {
+                    Table::Addresses => ("address list", export::addresses_csv(&self.address_items, &self.internal_address_items)),
+                    Table::Utxos => ("unspent list", export::utxos_csv(&self.utxo_items)),
+                    Table::Transactions => ("transaction list", export::transactions_csv(&self.transaction_items)),
+                    // only the wallet lists have a CSV button
+                    Table::AddressHistory | Table::TransactionInputs | Table::TransactionOutputs | Table::GainsYears | Table::Disposals => return Command::none(),
+                };
+
+                clipboard.write(csv);
+                return self.show_copied(format!("{} as CSV", name));
+            },
+            RuWalletMessage::SortTable(table, column) => {
+                self.data_table(table).sort_by(column);
+            },
+            RuWalletMessage::SearchTable(table, value) => {
+                self.data_table(table).set_search(value);
+            },
+            RuWalletMessage::AddressFilterSelected(filter) => {
+                self.address_table.filter = filter;
+                self.address_table.table.first_page();
+            },
+            RuWalletMessage::TransactionFilterSelected(filter) => {
+                self.transaction_table.filter = filter;
+                self.transaction_table.table.first_page();
+            },
+            RuWalletMessage::ChangePage(table, page) => {
+                self.data_table(table).set_page(page);
+            },
+            RuWalletMessage::PageInputChanged(table, value) => {
+                self.data_table(table).set_page_input(value);
+            },
+            RuWalletMessage::JumpToPage(table) => {
+                self.data_table(table).jump_to_page();
+            },
+            RuWalletMessage::UnitSelected(unit) => {
+                // amounts being typed are kept, converted to the new unit
+                convert_amount(&mut self.receive_view.amount_input_value, self.config.unit, unit);
+                convert_amount(&mut self.send_view.amount_input_value, self.config.unit, unit);
 
-                for utxo in wallet.list_unspent().unwrap().iter() {
+                self.config.unit = unit;
+                self.save_config();
 
-                    let addr = Address::from_script(&utxo.txout.script_pubkey, Network::Testnet).unwrap();
+                self.receive_view.update(&self.new_address, unit);
+            },
+            RuWalletMessage::ThemeSelected(theme) => {
+                self.config.theme = theme;
+                self.save_config();
+            },
+            RuWalletMessage::CurrencyInputChanged(value) => {
+                self.currency_input_value = value;
+            },
+            RuWalletMessage::PriceFileInputChanged(value) => {
+                self.price_file_input_value = value;
+            },
+            RuWalletMessage::LoadPrices => {
+                self.config.fiat.currency = self.currency_input_value.trim().to_uppercase();
+                self.config.fiat.price_file = self.price_file_input_value.trim().to_string();
+                self.save_config();
 
-                    let utxo_tx = (&mut self.transaction_items).iter_mut().find(
-                        |tr| tr.txid.to_string().eq(&utxo.outpoint.txid.to_string())
-                    );
+                self.currency_input_value = self.config.fiat.currency.clone();
+                self.load_prices();
+            },
+            RuWalletMessage::ExportFormatSelected(format) => {
+                self.export_view.format = format;
+            },
+            RuWalletMessage::ExportFromInputChanged(value) => {
+                self.export_view.from_input_value = value;
+            },
+            RuWalletMessage::ExportToInputChanged(value) => {
+                self.export_view.to_input_value = value;
+            },
+            RuWalletMessage::ExportDirectoryInputChanged(value) => {
+                self.export_view.directory_input_value = value;
+            },
+            RuWalletMessage::ExportList(table) => {
+                self.export_view.status = match self.export_list(table) {
+                    Ok(path) => format!("Exported to {}", path.display()),
+                    Err(e) => e,
+                };
+            },
+            RuWalletMessage::LotMethodSelected(method) => {
+                self.gains_view.method = method;
+            },
+            RuWalletMessage::BuildGainsReport => {
+                match self.build_gains_report() {
+                    Ok(report) => {
+                        self.gains_view.report = Some(report);
+                        self.gains_view.status.clear();
+                    },
+                    Err(e) => {
+                        self.gains_view.report = None;
+                        self.gains_view.status = e;
+                    },
+                }
+            },
+            RuWalletMessage::ExportGainsReport => {
+                if let Some(report) = &self.gains_view.report {
+                    let name = format!("gains-{}", self.gains_view.method.to_string().to_lowercase());
 
-                    let height = match utxo_tx {
-                        Some(tr) => tr.height,
-                        None => 0,
+                    self.gains_view.status = match self.write_export(&name, "csv", &export::gains_csv(report)) {
+                        Ok(path) => format!("Exported to {}", path.display()),
+                        Err(e) => e,
                     };
-
-                    self.utxo_items.push(
-                        UTXORow {
-                            txid: utxo.outpoint.txid.to_string(),
-                            vout: utxo.outpoint.vout,
-                            address: addr.to_string(),
-                            amount: utxo.txout.value,
-                            height
-                        }
-                    )
                 }
-
-                self.utxo_items.sort_by(|a, b| b.height.cmp(&a.height));
+            },
+            RuWalletMessage::SelectTab(tab) => {
+                self.tab = tab;
+            },
+            RuWalletMessage::ChartRangeSelected(range) => {
+                self.overview_view.select_range(range);
+            },
+            RuWalletMessage::HideCopied(generation) => {
+                if generation == self.copied_generation {
+                    self.copied = None;
+                }
+            },
+            RuWalletMessage::DismissNotifications => {
+                self.toasts.clear();
+            },
+            RuWalletMessage::ToggleNotificationHistory => {
+                self.notification_area.show_history = !self.notification_area.show_history;
+            },
+            RuWalletMessage::ClearNotificationHistory => {
+                self.notification_history.clear();
+                self.save_notification_history();
+            },
+            RuWalletMessage::NotifyIncomingToggled(notify_incoming) => {
+                self.config.notifications.notify_incoming = notify_incoming;
+                self.save_config();
+            },
+            RuWalletMessage::NotificationThresholdsInputChanged(value) => {
+                self.notification_area.thresholds_input_value = value;
+            },
+            RuWalletMessage::SaveNotificationSettings => {
+                let thresholds: Result<Vec<u32>, _> = self.notification_area.thresholds_input_value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::parse::<u32>)
+                    .collect();
+
+                match thresholds {
+                    Ok(mut thresholds) => {
+                        thresholds.sort_unstable();
+                        thresholds.dedup();
+                        self.notification_area.thresholds_input_value = format_thresholds(&thresholds);
+                        self.config.notifications.confirmation_thresholds = thresholds;
+                        self.save_config();
+                    },
+                    Err(_) => {
+                        self.error_message = "Confirmation thresholds must be whole numbers separated by commas".to_string();
+                    },
+                }
             },
         }
 
         Command::none()
     }
 
+    fn subscription(&self) -> Subscription<Self::Message> {
+        match (&self.wallet_service, self.watched_scripts.is_empty()) {
+            (Some(wallet_service), false) => {
+                let wallet_config = wallet_service.config();
+
+                chain_subscription::watch(wallet_config.id, wallet_config.server_list(), self.watched_scripts.clone())
+                    .map(Self::Message::ChainEvent)
+            },
+            _ => Subscription::none(),
+        }
+    }
+
     fn view(&mut self) -> Element<Self::Message> {
 
+        let palette = theme::palette(self.config.theme);
+
+        let sidebar = self.wallet_list.view(&self.config, palette);
+
+        let notification_area = self.notification_area.view(&self.toasts, &self.notification_history, &self.config.notifications, self.config.unit, palette);
+
         let title = Text::new("Rust Wallet")
             .font(ROBOTO_BOLD)
             .width(Length::Fill)
             .size(100)
-            .color([0.5, 0.5, 0.5])
+            .color(palette.muted)
             .horizontal_alignment(HorizontalAlignment::Center);
 
         let external_descriptor_input = TextInput::new(
@@ -204,7 +725,8 @@ impl Application for RuWallet {
         )
         .padding(15)
         .size(20)
-        .font(ROBOTO);
+        .font(ROBOTO)
+        .style(InputStyle(palette));
         //.on_submit(Self::Message::CreateWallet);
 
         let internal_descriptor_input = TextInput::new(
@@ -215,7 +737,8 @@ impl Application for RuWallet {
         )
         .padding(15)
         .size(20)
-        .font(ROBOTO);
+        .font(ROBOTO)
+        .style(InputStyle(palette));
         //.on_submit(Self::Message::CreateWallet);
 
         let create_wallet_button = Button::new(
@@ -223,469 +746,877 @@ impl Application for RuWallet {
             Text::new("Restore Wallet")
         )
         .padding(15)
+        .style(ButtonStyle(palette))
         .on_press(Self::Message::CreateWallet);
 
-        let mut content = Column::new()
+        let unit_pick_list = PickList::new(
+            &mut self.unit_pick_list_state,
+            &BITCOIN_UNITS[..],
+            Some(self.config.unit),
+            Self::Message::UnitSelected
+        )
+        .padding(10)
+        .text_size(18)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        let theme_pick_list = PickList::new(
+            &mut self.theme_pick_list_state,
+            &THEMES[..],
+            Some(self.config.theme),
+            Self::Message::ThemeSelected
+        )
+        .padding(10)
+        .text_size(18)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        let currency_input = TextInput::new(
+            &mut self.currency_input_state,
+            "Currency",
+            &mut self.currency_input_value,
+            Self::Message::CurrencyInputChanged
+        )
+        .width(Length::Units(100))
+        .padding(10)
+        .size(18)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let price_file_input = TextInput::new(
+            &mut self.price_file_input_state,
+            "Price History File (CSV or JSON)",
+            &mut self.price_file_input_value,
+            Self::Message::PriceFileInputChanged
+        )
+        .padding(10)
+        .size(18)
+        .font(ROBOTO)
+        .style(InputStyle(palette))
+        .on_submit(Self::Message::LoadPrices);
+
+        let load_prices_button = Button::new(
+            &mut self.load_prices_button_state,
+            Text::new("Load Prices")
+        )
+        .padding(10)
+        .style(ButtonStyle(palette))
+        .on_press(Self::Message::LoadPrices);
+
+        let display_settings = Row::new()
             .spacing(20)
-            .push(title)
-            .push(external_descriptor_input)
-            .push(internal_descriptor_input)
-            .push(create_wallet_button);
+            .align_items(Align::Center)
+            .push(
+                Text::new("Unit")
+                    .font(ROBOTO)
+                    .size(20)
+            )
+            .push(unit_pick_list)
+            .push(currency_input)
+            .push(price_file_input)
+            .push(load_prices_button)
+            .push(
+                Text::new("Theme")
+                    .font(ROBOTO)
+                    .size(20)
+            )
+            .push(theme_pick_list);
 
-        if !self.address_items.is_empty() {
+        let selected_network = self.config.selected_wallet().map(|w| w.network);
 
-            let address_list_title = Text::new("Address List")
-                .font(ROBOTO_BOLD)
-                .width(Length::Fill)
-                .size(35)
-                .color([0.5, 0.5, 0.5])
-                .horizontal_alignment(HorizontalAlignment::Left);
-
-            let mut address_table: Column<RuWalletMessage> = Column::new()
-                .width(iced::Length::FillPortion(1000))
-                .spacing(10);
-
-
-            let mut table_header: Row<RuWalletMessage> = Row::new()
-                .align_items(Align::Start)
-                .spacing(10);
-
-            table_header = table_header
-                .push(
-                    Text::new("Index")
-                        .font(ROBOTO)
-                        .width(Length::Units(50))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Type")
-                        .font(ROBOTO)
-                        .width(Length::Units(110))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Address")
-                        .font(ROBOTO)
-                        .width(Length::Units(410))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Balance (sats)")
-                        .font(ROBOTO)
-                        .width(Length::Units(150))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Tx Count")
-                        .font(ROBOTO)
-                        .width(Length::Units(90))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                );
+        let network_pick_list = PickList::new(
+            &mut self.network_pick_list_state,
+            &NETWORKS[..],
+            selected_network,
+            Self::Message::NetworkSelected
+        )
+        .padding(15)
+        .text_size(20)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        let electrum_url_input = TextInput::new(
+            &mut self.electrum_url_input_state,
+            "Enter Electrum Server URL",
+            &mut self.electrum_url_input_value,
+            Self::Message::ElectrumUrlInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let server_settings = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(network_pick_list)
+            .push(electrum_url_input);
+
+        let fallback_urls_input = TextInput::new(
+            &mut self.fallback_urls_input_state,
+            "Fallback Servers (comma separated)",
+            &mut self.fallback_urls_input_value,
+            Self::Message::FallbackUrlsInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
 
-            address_table = address_table.push(table_header);
+        let paranoid = self.config.selected_wallet().map(|w| w.paranoid).unwrap_or(false);
 
+        let paranoid_checkbox = Checkbox::new(
+            paranoid,
+            "Paranoid mode",
+            Self::Message::ParanoidToggled
+        )
+        .text_size(20)
+        .font(ROBOTO)
+        .style(CheckboxStyle(palette));
 
-            for addr_item in &self.address_items {
+        let failover_settings = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(fallback_urls_input)
+            .push(paranoid_checkbox);
+
+        let proxy_address_input = TextInput::new(
+            &mut self.proxy_address_input_state,
+            "SOCKS5 Proxy (e.g. 127.0.0.1:9050)",
+            &mut self.proxy_address_input_value,
+            Self::Message::ProxyAddressInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let proxy_username_input = TextInput::new(
+            &mut self.proxy_username_input_state,
+            "Proxy User",
+            &mut self.proxy_username_input_value,
+            Self::Message::ProxyUsernameInputChanged
+        )
+        .width(Length::Units(200))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let proxy_password_input = TextInput::new(
+            &mut self.proxy_password_input_state,
+            "Proxy Password",
+            &mut self.proxy_password_input_value,
+            Self::Message::ProxyPasswordInputChanged
+        )
+        .password()
+        .width(Length::Units(200))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
 
-                let mut table_row = Row::new()
-                    .align_items(Align::Start)
-                    .spacing(10);
+        let tor_only = self.config.selected_wallet().map(|w| w.proxy.tor_only).unwrap_or(false);
 
-                let addr_index_text = Text::new(addr_item.index.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(50))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+        let tor_only_checkbox = Checkbox::new(
+            tor_only,
+            "Tor only",
+            Self::Message::TorOnlyToggled
+        )
+        .text_size(20)
+        .font(ROBOTO)
+        .style(CheckboxStyle(palette));
 
-                let addr_type_text = Text::new("receiving")
-                    .font(ROBOTO)
-                    .width(Length::Units(110))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+        let proxy_settings = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(proxy_address_input)
+            .push(proxy_username_input)
+            .push(proxy_password_input)
+            .push(tor_only_checkbox);
+
+        let tls_config = self.config.selected_wallet().map(|w| w.tls.clone()).unwrap_or_default();
+
+        let tls_mode_pick_list = PickList::new(
+            &mut self.tls_mode_pick_list_state,
+            &TLS_MODES[..],
+            Some(tls_config.mode),
+            Self::Message::TlsModeSelected
+        )
+        .padding(15)
+        .text_size(20)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
 
-                let addr_text = Text::new(addr_item.address.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(410))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+        let mut tls_settings = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(tls_mode_pick_list);
+
+        match tls_config.mode {
+            TlsMode::PinnedCertificate => {
+                let certificate_path_input = TextInput::new(
+                    &mut self.certificate_path_input_state,
+                    "Certificate File (PEM or DER)",
+                    &mut self.certificate_path_input_value,
+                    Self::Message::CertificatePathInputChanged
+                )
+                .padding(15)
+                .size(20)
+                .font(ROBOTO)
+                .style(InputStyle(palette));
 
-                let addr_balance_text = Text::new(addr_item.balance.to_string())
+                tls_settings = tls_settings.push(certificate_path_input);
+            },
+            TlsMode::TrustOnFirstUse if !tls_config.fingerprint.is_empty() => {
+                let fingerprint_text = Text::new(format!("Trusted: {}", tls_config.fingerprint))
                     .font(ROBOTO)
-                    .width(Length::Units(150))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+                    .size(16)
+                    .width(Length::Fill);
 
-                let addr_tx_count_text = Text::new(addr_item.tx_count.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(90))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+                let forget_fingerprint_button = Button::new(
+                    &mut self.forget_fingerprint_button_state,
+                    Text::new("Forget")
+                )
+                .padding(10)
+                .style(ButtonStyle(palette))
+                .on_press(Self::Message::ForgetFingerprint);
 
-                table_row = table_row
-                    .push(addr_index_text)
-                    .push(addr_type_text)
-                    .push(addr_text)
-                    .push(addr_balance_text)
-                    .push(addr_tx_count_text);
+                tls_settings = tls_settings
+                    .push(fingerprint_text)
+                    .push(forget_fingerprint_button);
+            },
+            _ => {},
+        }
 
-                address_table = address_table.push(table_row);
+        let mut content = Column::new()
+            .spacing(20)
+            .push(title)
+            .push(notification_area)
+            .push(display_settings);
 
-            }
+        if let Some(copied) = &self.copied {
+            let copied_text = Text::new(format!("Copied {}", copied))
+                .font(ROBOTO)
+                .width(Length::Fill)
+                .size(18)
+                .color(palette.positive);
 
+            content = content.push(copied_text);
+        }
 
-            for addr_item in &self.internal_address_items {
+        if selected_network.is_none() {
+            let no_wallet_text = Text::new("Create a wallet in the sidebar to get started")
+                .font(ROBOTO)
+                .width(Length::Fill)
+                .size(20)
+                .color(palette.muted)
+                .horizontal_alignment(HorizontalAlignment::Center);
 
-                let mut table_row = Row::new()
-                    .align_items(Align::Start)
-                    .spacing(10);
+            content = content.push(no_wallet_text);
 
-                let addr_index_text = Text::new(addr_item.index.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(50))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+            return with_sidebar(sidebar, &mut self.scroll, content, palette);
+        }
 
-                let addr_type_text = Text::new("change")
-                    .font(ROBOTO)
-                    .width(Length::Units(110))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+        if let Some(transaction_detail) = &self.transaction_detail {
+            content = content.push(self.transaction_detail_view.view(transaction_detail, self.config.unit, palette));
 
-                let addr_text = Text::new(addr_item.address.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(410))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+            return with_sidebar(sidebar, &mut self.scroll, content, palette);
+        }
 
-                let addr_balance_text = Text::new(addr_item.balance.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(150))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+        if let Some(address_detail) = &self.address_detail {
+            content = content.push(self.address_detail_view.view(address_detail, self.config.unit, palette));
 
-                let addr_tx_count_text = Text::new(addr_item.tx_count.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(90))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+            return with_sidebar(sidebar, &mut self.scroll, content, palette);
+        }
 
-                table_row = table_row
-                    .push(addr_index_text)
-                    .push(addr_type_text)
-                    .push(addr_text)
-                    .push(addr_balance_text)
-                    .push(addr_tx_count_text);
+        if self.wallet_service.is_some() {
+            let tab_button = |state, label, tab: Tab, selected: Tab| {
+                let button = Button::new(state, Text::new(label).font(ROBOTO_BOLD))
+                    .padding(10)
+                    .style(ButtonStyle(palette));
 
-                address_table = address_table.push(table_row);
+                if tab == selected {
+                    button
+                } else {
+                    button.on_press(Self::Message::SelectTab(tab))
+                }
+            };
 
-            }
+            content = content.push(
+                Row::new()
+                    .spacing(10)
+                    .push(tab_button(&mut self.wallet_tab_button_state, "Wallet", Tab::Wallet, self.tab))
+                    .push(tab_button(&mut self.overview_tab_button_state, "Overview", Tab::Overview, self.tab))
+            );
 
-            content = content
-                .push(address_list_title)
-                .push(address_table);
+            if self.tab == Tab::Overview {
+                if let Some(balance_text) = self.balance_text() {
+                    content = content.push(
+                        Text::new(balance_text)
+                            .font(ROBOTO_BOLD)
+                            .width(Length::Fill)
+                            .size(25)
+                    );
+                }
+
+                content = content.push(self.overview_view.view(&self.transaction_items, self.config.unit, notifications::now(), palette));
+
+                return with_sidebar(sidebar, &mut self.scroll, content, palette);
+            }
         }
 
-        // show new address
-        if !self.new_address.is_empty() {
+        content = content
+            .push(server_settings)
+            .push(failover_settings)
+            .push(proxy_settings)
+            .push(tls_settings)
+            .push(external_descriptor_input)
+            .push(internal_descriptor_input)
+            .push(create_wallet_button);
 
-            let new_address_title = Text::new("Current Receive Address")
-                .font(ROBOTO_BOLD)
+        if !self.error_message.is_empty() {
+            let error_text = Text::new(&self.error_message)
+                .font(ROBOTO)
                 .width(Length::Fill)
-                .size(35)
-                .color([0.5, 0.5, 0.5])
-                .horizontal_alignment(HorizontalAlignment::Left);
+                .size(20)
+                .color(palette.negative);
 
-            let new_address_text =Text::new(&self.new_address)
+            content = content.push(error_text);
+        }
+
+        for server_warning in &self.server_warnings {
+            let warning_text = Text::new(server_warning)
                 .font(ROBOTO)
                 .width(Length::Fill)
                 .size(20)
-                .horizontal_alignment(HorizontalAlignment::Left);
+                .color(palette.warning);
+
+            content = content.push(warning_text);
+        }
+
+        if let Some(balance_text) = self.balance_text() {
+            content = content.push(
+                Text::new(balance_text)
+                    .font(ROBOTO_BOLD)
+                    .width(Length::Fill)
+                    .size(25)
+            );
+        }
 
+        if !self.address_items.is_empty() {
+            content = content.push(self.address_table.view(&self.address_items, &self.internal_address_items, self.config.unit, palette));
+        }
+
+        // show new address
+        if !self.new_address.is_empty() {
             content = content
-                .push(new_address_title)
-                .push(new_address_text);
+                .push(self.receive_view.view(self.config.unit, palette))
+                .push(self.send_view.view(self.config.unit, palette));
         }
 
         if !self.utxo_items.is_empty() {
+            content = content.push(self.utxo_table.view(&self.utxo_items, self.config.unit, palette));
+        }
 
-            let unspent_list_title = Text::new("Unspent List")
-                .font(ROBOTO_BOLD)
-                .width(Length::Fill)
-                .size(35)
-                .color([0.5, 0.5, 0.5])
-                .horizontal_alignment(HorizontalAlignment::Left);
-
-            let mut unspent_table: Column<RuWalletMessage> = Column::new()
-                .width(iced::Length::Fill)
-                .spacing(10);
-
-            let mut table_header: Row<RuWalletMessage> = Row::new()
-                .align_items(Align::Start)
-                .spacing(10);
-
-            table_header = table_header
-                .push(
-                    Text::new("Output Point")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(610))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Address")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(410))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Amount (sats)")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(150))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Right)
-                )
-                .push(
-                    Text::new("Height")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(110))
-                        .size(18)
-                        .horizontal_alignment(HorizontalAlignment::Right)
-                );
+        if self.wallet_service.is_some() {
+            content = content
+                .push(self.export_view.view(palette))
+                .push(self.gains_view.view(self.config.unit, &self.config.fiat.currency, palette));
+        }
 
-            unspent_table = unspent_table.push(table_header);
+        if !self.transaction_items.is_empty() {
+            content = content.push(self.transaction_table.view(&self.transaction_items, self.config.unit, self.price_history.as_ref(), &self.config.fiat.currency, palette));
+        }
 
-            for utxo_item in &self.utxo_items {
+        with_sidebar(sidebar, &mut self.scroll, content, palette)
+    }
+}
 
-                let mut table_row: Row<RuWalletMessage> = Row::new()
-                    .align_items(Align::Start)
-                    .spacing(10);
+fn convert_amount(value: &mut String, from: BitcoinUnit, to: BitcoinUnit) {
+    if let Ok(sats) = from.parse(value) {
+        *value = to.format(sats);
+    }
+}
 
-                let txid_vout = format!("{}:{}", utxo_item.txid.to_string(), utxo_item.vout);
+fn format_thresholds(thresholds: &[u32]) -> String {
+    thresholds.iter().map(u32::to_string).collect::<Vec<String>>().join(", ")
+}
 
-                let txid_text = Text::new(txid_vout)
-                    .font(ROBOTO)
-                    .width(Length::Units(610))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+fn with_sidebar<'a>(sidebar: Element<'a, RuWalletMessage>, scroll: &'a mut scrollable::State, content: Column<'a, RuWalletMessage>, palette: Palette) -> Element<'a, RuWalletMessage> {
 
-                let address_text = Text::new(&utxo_item.address)
-                    .font(ROBOTO)
-                    .width(Length::Units(410))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+    let main_content = Scrollable::new(scroll)
+        .padding(40)
+        .width(Length::Fill)
+        .push(
+            Container::new(content).width(Length::Fill).center_x(),
+        );
 
-                let address_amount = Text::new(utxo_item.amount.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(150))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+    // the containers set the background and the text colour of everything inside
+    let sidebar = Container::new(sidebar)
+        .height(Length::Fill)
+        .style(ContainerStyle { palette, surface: true });
+
+    Container::new(
+        Row::new()
+            .push(sidebar)
+            .push(main_content)
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .style(ContainerStyle { palette, surface: false })
+    .into()
+}
 
-                let height = Text::new(utxo_item.height.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(110))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+impl RuWallet {
 
-                table_row = table_row
-                    .push(txid_text)
-                    .push(address_text)
-                    .push(address_amount)
-                    .push(height);
+    fn restore_wallet(&mut self) -> Result<(), bdk::Error> {
+        if self.store_wallet_inputs()?.is_none() {
+            return Ok(());
+        }
 
-                unspent_table = unspent_table.push(table_row);
-            }
+        let wallet_config = match self.config.selected_wallet_mut() {
+            Some(wallet_config) => {
+                if wallet_config.verify_server()? {
+                    println!("Trusting certificate {} on first use", wallet_config.tls.fingerprint);
+                }
+                wallet_config.clone()
+            },
+            None => return Ok(()),
+        };
 
-            content = content
-                .push(unspent_list_title)
-                .push(unspent_table);
+        self.config.save().map_err(|e| bdk::Error::Generic(format!("Cannot save the settings: {}", e)))?;
+
+        // keep the open wallet and its connection around, a repeated restore only re-syncs
+        let wallet_service = match self.wallet_service.take() {
+            Some(wallet_service) if wallet_service.config() == &wallet_config => {
+                wallet_service.sync()?;
+                wallet_service
+            },
+            previous => {
+                // the old wallet holds the lock on its database, release it before reopening
+                if let Some(previous) = previous {
+                    previous.close();
+                }
+                WalletService::restore(&wallet_config)?
+            },
+        };
+
+        self.address_items = wallet_service.addresses(KeychainKind::External, 10)?;
+
+        self.internal_address_items = wallet_service.addresses(KeychainKind::Internal, 10)?;
+
+        self.new_address = wallet_service.new_address()?;
+
+        self.receive_view.update(&self.new_address, self.config.unit);
+
+        self.transaction_items = wallet_service.transactions()?;
+
+        self.utxo_items = wallet_service.unspent()?;
+
+        if wallet_config.paranoid {
+            self.server_warnings = wallet_service.cross_check(5)?;
         }
 
-        if !self.transaction_items.is_empty() {
+        self.watched_scripts = wallet_service.watched_scripts()?;
 
-            let tx_list_title = Text::new("Transaction List")
-                .font(ROBOTO_BOLD)
-                .width(Length::Fill)
-                .size(35)
-                .color([0.5, 0.5, 0.5])
-                .horizontal_alignment(HorizontalAlignment::Left);
-
-            let mut transaaction_table: Column<RuWalletMessage> = Column::new()
-                .width(iced::Length::Fill)
-                .spacing(10);
-
-            let mut table_header: Row<RuWalletMessage> = Row::new()
-                .align_items(Align::Start)
-                .spacing(10);
-
-            table_header = table_header
-                .push(
-                    Text::new("Transaction Id")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(610))
-                        .size(20)
-                        .horizontal_alignment(HorizontalAlignment::Left)
-                )
-                .push(
-                    Text::new("Amount (sats)")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(150))
-                        .size(20)
-                        .horizontal_alignment(HorizontalAlignment::Right)
-                )
-                .push(
-                    Text::new("Height")
-                        .font(ROBOTO_BOLD)
-                        .width(Length::Units(110))
-                        .size(20)
-                        .horizontal_alignment(HorizontalAlignment::Right)
-                );
+        self.tip_height = wallet_service.tip_height()?;
+
+        self.wallet_service = Some(wallet_service);
 
-            transaaction_table = transaaction_table.push(table_header);
+        Ok(())
+    }
 
-            for transaction_item in &self.transaction_items {
+    // sum of the unspent outputs, with its fiat value when prices are loaded
+    fn balance_text(&self) -> Option<String> {
+        if self.utxo_items.is_empty() {
+            return None;
+        }
 
-                let mut table_row: Row<RuWalletMessage> = Row::new()
-                    .align_items(Align::Start)
-                    .spacing(10);
+        let balance: u64 = self.utxo_items.iter().map(|utxo| utxo.amount).sum();
 
-                let txid_text = Text::new(&transaction_item.txid)
-                    .font(ROBOTO)
-                    .width(Length::Units(610))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Left);
+        let mut balance_text = format!("Balance: {}", self.config.unit.format_with_unit(balance));
 
-                let amount_text = Text::new(transaction_item.amount.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(150))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+        if let Some(value) = self.price_history.as_ref().and_then(|prices| prices.value(balance.into(), None)) {
+            balance_text = format!("{} ({})", balance_text, format_fiat(value, &self.config.fiat.currency));
+        }
 
-                let height_amount = Text::new(transaction_item.height.to_string())
-                    .font(ROBOTO)
-                    .width(Length::Units(110))
-                    .size(20)
-                    .horizontal_alignment(HorizontalAlignment::Right);
+        Some(balance_text)
+    }
+
+    fn data_table(&mut self, table: Table) -> &mut DataTable {
+        match table {
+            Table::Addresses => &mut self.address_table.table,
+            Table::Utxos => &mut self.utxo_table.table,
+            Table::Transactions => &mut self.transaction_table.table,
+            Table::AddressHistory => &mut self.address_detail_view.history_table,
+            Table::TransactionInputs => &mut self.transaction_detail_view.inputs_table,
+            Table::TransactionOutputs => &mut self.transaction_detail_view.outputs_table,
+            Table::GainsYears => &mut self.gains_view.years_table,
+            Table::Disposals => &mut self.gains_view.disposals_table,
+        }
+    }
+
+    // writes one of the wallet lists to the export directory, returns the file written
+    fn export_list(&self, table: Table) -> Result<PathBuf, String> {
+        let wallet_service = self.wallet_service.as_ref().ok_or("No wallet loaded")?;
+        let format = self.export_view.format;
+
+        let (list_name, content) = match table {
+            Table::Transactions => {
+                let range = DateRange::parse(&self.export_view.from_input_value, &self.export_view.to_input_value)?;
+                let history = wallet_service.history(self.tip_height).map_err(|e| e.to_string())?;
+
+                ("transactions", export::export_history(&history, &range, format))
+            },
+            Table::Addresses => ("addresses", export::export_addresses(&self.address_items, &self.internal_address_items, format)),
+            Table::Utxos => ("unspent", export::export_utxos(&self.utxo_items, format)),
+            Table::AddressHistory | Table::TransactionInputs | Table::TransactionOutputs | Table::GainsYears | Table::Disposals => {
+                return Err("Only the wallet lists can be exported".to_string());
+            },
+        };
+
+        self.write_export(list_name, format.extension(), &content)
+    }
+
+    // <wallet>-<name>-<date>.<extension> in the export directory
+    fn write_export(&self, name: &str, extension: &str, content: &str) -> Result<PathBuf, String> {
+        let wallet_name = self.config.selected_wallet().map(|w| w.name.clone()).unwrap_or_default();
+        let date = &format_timestamp(notifications::now())[..10];
+
+        let directory = Path::new(self.export_view.directory_input_value.trim());
+        let path = directory.join(format!("{}-{}-{}.{}", wallet_name, name, date, extension));
+
+        fs::write(&path, content).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+
+        Ok(path)
+    }
+
+    // Gains of every wallet on the network of the selected one: moving coins
+    // between them is only recognized as a transfer when both sides are known.
+    fn build_gains_report(&self) -> Result<GainsReport, String> {
+        let prices = self.price_history.as_ref().ok_or("Load a price file to value the lots")?;
+        let wallet_service = self.wallet_service.as_ref().ok_or("No wallet loaded")?;
+        let network = wallet_service.network();
 
-                table_row = table_row
-                    .push(txid_text)
-                    .push(amount_text)
-                    .push(height_amount);
+        let mut histories = vec![wallet_service.history(self.tip_height).map_err(|e| e.to_string())?];
 
-                transaaction_table = transaaction_table.push(table_row);
+        for wallet_config in &self.config.wallets {
+            if Some(wallet_config.id) == self.config.selected || wallet_config.network != network || wallet_config.external_descriptor.is_empty() {
+                continue;
             }
 
-            content = content
-                .push(tx_list_title)
-                .push(transaaction_table);
+            // the local database is enough, the other wallets aren't synced here
+            let other_wallet = WalletService::open(wallet_config)
+                .map_err(|e| format!("Cannot open wallet '{}': {}", wallet_config.name, e))?;
+
+            histories.push(other_wallet.history(self.tip_height).map_err(|e| e.to_string())?);
         }
 
-        Scrollable::new(&mut self.scroll)
-            .padding(40)
-            .width(Length::Units(13400))
-            .push(
-                Container::new(content).width(Length::Fill).center_x(),
-            )
-            .into()
+        GainsReport::build(&histories, prices, self.gains_view.method)
     }
-}
 
-impl RuWallet {
+    // a change that cannot be written is only kept until the app closes
+    fn save_config(&mut self) {
+        if let Err(e) = self.config.save() {
+            self.error_message = format!("Cannot save the settings: {}", e);
+        }
+    }
 
-    // additional function non-related to GUI
+    fn load_prices(&mut self) {
+        let price_file = self.config.fiat.price_file.clone();
 
-    async fn generate_wallet(&self) -> Wallet<ElectrumBlockchain, MemoryDatabase> {
-        let network = Network::Testnet;
+        if price_file.is_empty() {
+            self.price_history = None;
+            return;
+        }
 
-        let electrum_url = "ssl://electrum.blockstream.info:60002";
+        match PriceHistory::load(Path::new(&price_file)) {
+            Ok(price_history) => self.price_history = Some(price_history),
+            Err(e) => {
+                self.price_history = None;
+                self.error_message = e;
+            },
+        }
+    }
 
-        let wallet = w_electrum::load_or_create_wallet(electrum_url,
-            &network,
-            &self.external_descriptor_input_value,
-            &self.internal_descriptor_input_value
-        );
+    fn show_transaction(&mut self, txid: &str) {
+        let detail = match &self.wallet_service {
+            Some(wallet_service) => wallet_service.transaction_detail(txid, self.tip_height),
+            None => Ok(None),
+        };
 
-        wallet
+        match detail {
+            Ok(detail) => self.transaction_detail = detail,
+            Err(e) => self.error_message = e.to_string(),
+        }
     }
 
-    async fn get_external_addresses(&self, wallet: &Wallet<ElectrumBlockchain, MemoryDatabase>) -> Vec::<AddressRow> {
+    fn show_address(&mut self, address: &str) {
+        let detail = match &self.wallet_service {
+            Some(wallet_service) => wallet_service.address_detail(address),
+            None => Ok(None),
+        };
+
+        match detail {
+            Ok(detail) => self.address_detail = detail,
+            Err(e) => self.error_message = e.to_string(),
+        }
+    }
 
-        let electrum_url = "ssl://electrum.blockstream.info:60002";
+    // shows the "copied" indicator for two seconds
+    fn show_copied(&mut self, copied: String) -> Command<RuWalletMessage> {
+        self.copied_generation += 1;
+        self.copied = Some(copied);
+
+        let generation = self.copied_generation;
+
+        Command::perform(async_std::task::sleep(Duration::from_secs(2)), move |_| RuWalletMessage::HideCopied(generation))
+    }
 
-        let mut scripts = Vec::<Script>::new();
+    // builds, signs and broadcasts the payment entered on the send form
+    fn send(&mut self) -> Result<(), String> {
+        let wallet_service = self.wallet_service.as_ref().ok_or("Restore the wallet first")?;
 
-        for n in 0..10 {
-            let address_info = wallet.get_address(AddressIndex::Peek(n)).unwrap();
+        let address = Address::from_str(self.send_view.recipient_input_value.trim()).map_err(|e| e.to_string())?;
+        let amount = self.config.unit.parse(&self.send_view.amount_input_value)?;
 
-            scripts.push(address_info.script_pubkey());
+        let fee_rate = match self.send_view.fee_rate_input_value.trim() {
+            "" => None,
+            fee_rate => Some(fee_rate.parse::<f32>().map_err(|_| "Invalid fee rate")?),
+        };
 
+        let (mut psbt, _details) = wallet_service.create_psbt(&[(address, amount)], fee_rate).map_err(|e| e.to_string())?;
+
+        if !wallet_service.sign(&mut psbt).map_err(|e| e.to_string())? {
+            self.send_view.status = format!("Needs more signatures: {}", psbt);
+            return Ok(());
         }
 
-        let additional_addr_info =
-            w_electrum::get_batch_history_and_balance(electrum_url, &scripts);
+        let txid = wallet_service.broadcast(&psbt.extract_tx()).map_err(|e| e.to_string())?;
 
-        let mut result = Vec::<AddressRow>::new();
+        wallet_service.sync().map_err(|e| e.to_string())?;
+        self.transaction_items = wallet_service.transactions().map_err(|e| e.to_string())?;
+        self.utxo_items = wallet_service.unspent().map_err(|e| e.to_string())?;
 
-        for aai in additional_addr_info {
-            result.push(
-                AddressRow {
-                    index: aai.index,
-                    address: aai.address,
-                    balance: aai.balance,
-                    tx_count: aai.tx_count
-                }
-            );
+        self.send_view.clear();
+        self.send_view.status = format!("Sent {}", txid);
+
+        Ok(())
+    }
+
+    // applies a live notification. A new tip only moves the confirmation counts, changed
+    // scripts are synced on a thread of their own and picked up by `finish_script_sync`
+    fn handle_chain_event(&mut self, event: ChainEvent) -> Result<Command<RuWalletMessage>, bdk::Error> {
+        let wallet_service = match &self.wallet_service {
+            Some(wallet_service) => wallet_service,
+            None => return Ok(Command::none()),
+        };
+
+        match event {
+            ChainEvent::NewTip(height) => {
+                let transactions = self.transaction_items.clone();
+                let previous_tip = self.tip_height;
+                self.tip_height = height;
+
+                self.announce_changes(&transactions, previous_tip)?;
+            },
+            ChainEvent::ScriptsChanged(scripts) => {
+                let wallet_id = wallet_service.config().id;
+                let script_sync = wallet_service.script_sync();
+
+                let sync = async_std::task::spawn_blocking(move || script_sync.run(&scripts).map_err(|e| e.to_string()));
+
+                return Ok(Command::perform(sync, move |result| RuWalletMessage::ScriptsSynced(wallet_id, result)));
+            },
+            ChainEvent::Disconnected(e) => {
+                self.error_message = format!("Live updates interrupted: {}", e);
+            },
         }
 
-        result
+        Ok(Command::none())
     }
 
-    async fn get_internal_addresses(&self, wallet: &Wallet<ElectrumBlockchain, MemoryDatabase>) -> Vec::<AddressRow> {
+    // reloads the lists from the database once the changed scripts are synced
+    fn finish_script_sync(&mut self, wallet_id: u32, changed: &[AddressRow]) -> Result<(), bdk::Error> {
+        let wallet_service = match &self.wallet_service {
+            // the result of a wallet closed in the meantime
+            Some(wallet_service) if wallet_service.config().id == wallet_id => wallet_service,
+            _ => return Ok(()),
+        };
+
+        let previous_transactions = self.transaction_items.clone();
 
-        let electrum_url = "ssl://electrum.blockstream.info:60002";
+        refresh_addresses(&mut self.address_items, changed);
+        refresh_addresses(&mut self.internal_address_items, changed);
 
-        let mut scripts = Vec::<Script>::new();
+        self.transaction_items = wallet_service.transactions()?;
+        self.utxo_items = wallet_service.unspent()?;
 
-        for n in 0..10 {
-            let address_info = self.peek_change_address(wallet, n).unwrap();
+        // a newly used address moves the derivation index, which restarts the subscription
+        self.watched_scripts = wallet_service.watched_scripts()?;
 
-            scripts.push(address_info.script_pubkey());
+        self.announce_changes(&previous_transactions, self.tip_height)
+    }
 
+    // turns new transactions and crossed confirmation thresholds into toasts and history entries
+    fn announce_changes(&mut self, previous_transactions: &[TransactionRow], previous_tip: u32) -> Result<(), bdk::Error> {
+        let wallet_service = match &self.wallet_service {
+            Some(wallet_service) => wallet_service,
+            None => return Ok(()),
+        };
+
+        let changes = notifications::detect_changes(
+            previous_transactions, previous_tip,
+            &self.transaction_items, self.tip_height,
+            &self.config.notifications);
+
+        if changes.is_empty() {
+            return Ok(());
         }
 
-        let additional_addr_info =
-            w_electrum::get_batch_history_and_balance(electrum_url, &scripts);
+        let wallet_name = wallet_service.config().name.clone();
+
+        for change in changes {
+            let notification = match change {
+                TransactionChange::Incoming { txid, amount } => {
+                    let (address, label) = wallet_service.receiving_address(&txid)?.unwrap_or_default();
+
+                    Notification {
+                        timestamp: notifications::now(),
+                        wallet_name: wallet_name.clone(),
+                        txid,
+                        kind: NotificationKind::Incoming { amount, address, label }
+                    }
+                },
+                TransactionChange::Confirmed { txid, confirmations } => Notification {
+                    timestamp: notifications::now(),
+                    wallet_name: wallet_name.clone(),
+                    txid,
+                    kind: NotificationKind::Confirmed { confirmations }
+                },
+            };
+
+            self.notification_history.push(notification.clone());
+            self.toasts.push(notification);
+        }
 
-        let mut result = Vec::<AddressRow>::new();
+        self.save_notification_history();
 
-        for aai in additional_addr_info {
-            result.push(
-                AddressRow {
-                    index: aai.index,
-                    address: aai.address,
-                    balance: aai.balance,
-                    tx_count: aai.tx_count
-                }
-            );
+        Ok(())
+    }
+
+    fn save_notification_history(&mut self) {
+        if let Err(e) = self.notification_history.save() {
+            self.error_message = format!("Cannot save the notification history: {}", e);
         }
+    }
 
-        result
+    fn clear_wallet_items(&mut self) {
+        self.address_items.clear();
+        self.new_address.clear();
+        self.receive_view.clear();
+        self.send_view.clear();
+        self.internal_address_items.clear();
+        self.utxo_items.clear();
+        self.transaction_items.clear();
+        self.transaction_detail = None;
+        self.address_detail = None;
+        self.watched_scripts.clear();
     }
 
+    // loads the inputs of the selected wallet into the form
+    fn load_selected_wallet(&mut self) {
+        self.clear_wallet_items();
+        self.error_message.clear();
+        self.server_warnings.clear();
+        self.wallet_service = None;
+
+        match self.config.selected_wallet() {
+            Some(wallet_config) => {
+                self.wallet_list.name_input_value = wallet_config.name.clone();
+                self.electrum_url_input_value = wallet_config.electrum_url.clone();
+                self.fallback_urls_input_value = wallet_config.fallback_urls.join(", ");
+                self.proxy_address_input_value = wallet_config.proxy.address.clone();
+                self.proxy_username_input_value = wallet_config.proxy.username.clone();
+                self.proxy_password_input_value = wallet_config.proxy.password.clone();
+                self.certificate_path_input_value = wallet_config.tls.certificate_path.clone();
+                self.external_descriptor_input_value = wallet_config.external_descriptor.clone();
+                self.internal_descriptor_input_value = wallet_config.internal_descriptor.clone();
+            },
+            None => {
+                self.wallet_list.name_input_value.clear();
+                self.electrum_url_input_value.clear();
+                self.fallback_urls_input_value.clear();
+                self.proxy_address_input_value.clear();
+                self.proxy_username_input_value.clear();
+                self.proxy_password_input_value.clear();
+                self.certificate_path_input_value.clear();
+                self.external_descriptor_input_value.clear();
+                self.internal_descriptor_input_value.clear();
+            },
+        }
+    }
 
-    fn peek_change_address(&self, wallet: &Wallet<ElectrumBlockchain, MemoryDatabase>, index: u32) -> Result<AddressInfo, Error> {
+    // the open wallet and its shared clients keep the settings they were opened with,
+    // once those change they are closed and the next restore reconnects with the new ones
+    fn close_stale_wallet(&mut self) {
+        let stale = match &self.wallet_service {
+            Some(wallet_service) => self.config.selected_wallet() != Some(wallet_service.config()),
+            None => false,
+        };
+
+        if stale {
+            if let Some(wallet_service) = self.wallet_service.take() {
+                wallet_service.close();
+            }
+        }
+    }
 
-        let result_descriptor = wallet.get_descriptor_for_keychain(bdk::KeychainKind::Internal);
+    // saves the form inputs into the selected wallet and returns its updated config
+    fn store_wallet_inputs(&mut self) -> Result<Option<WalletConfig>, bdk::Error> {
+        let external_descriptor = self.external_descriptor_input_value.trim().to_string();
+        let internal_descriptor = self.internal_descriptor_input_value.trim().to_string();
+        let electrum_url = self.electrum_url_input_value.trim().to_string();
+
+        let wallet_config = match self.config.selected_wallet_mut() {
+            Some(wallet_config) => wallet_config,
+            None => return Ok(None),
+        };
+
+        // the database is bound to the descriptors, so start over if they changed
+        if wallet_config.external_descriptor != external_descriptor
+            || wallet_config.internal_descriptor != internal_descriptor {
+
+            // close the open database before removing it
+            self.wallet_service = None;
+
+            let db_path = wallet_config.database_path();
+            if db_path.exists() {
+                fs::remove_dir_all(&db_path).map_err(|e| {
+                    bdk::Error::Generic(format!("Cannot remove the old wallet database {}: {}", db_path.display(), e))
+                })?;
+            }
+        }
 
-        result_descriptor
-            .as_derived(index, wallet.secp_ctx())
-            .address(wallet.network())
-            .map(|address| AddressInfo { index, address })
-            .map_err(|_| Error::ScriptDoesntHaveAddressForm)
+        wallet_config.external_descriptor = external_descriptor;
+        wallet_config.internal_descriptor = internal_descriptor;
+        wallet_config.electrum_url = electrum_url;
+        wallet_config.fallback_urls = self.fallback_urls_input_value
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        wallet_config.proxy.address = self.proxy_address_input_value.trim().to_string();
+        wallet_config.proxy.username = self.proxy_username_input_value.clone();
+        wallet_config.proxy.password = self.proxy_password_input_value.clone();
+        wallet_config.tls.certificate_path = self.certificate_path_input_value.trim().to_string();
+
+        let wallet_config = wallet_config.clone();
+
+        self.config.save().map_err(|e| bdk::Error::Generic(format!("Cannot save the settings: {}", e)))?;
+
+        Ok(Some(wallet_config))
     }
-}
\ No newline at end of file
+}
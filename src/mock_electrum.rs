@@ -0,0 +1,249 @@
+use bdk::bitcoin::consensus::encode::{deserialize, serialize_hex};
+use bdk::bitcoin::hashes::hex::{FromHex, ToHex};
+use bdk::bitcoin::hashes::{sha256, Hash};
+use bdk::bitcoin::{BlockHash, BlockHeader, OutPoint, Script, Transaction, TxMerkleNode, Txid};
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::electrum::{ElectrumConnector, ServerSettings, UrlConnector};
+
+// time of the first mock block, later blocks are 10 minutes apart
+const GENESIS_TIME: u32 = 1_600_000_000;
+
+// Scripted chain state served by `MockElectrumServer`.
+#[derive(Debug, Default, Clone)]
+pub struct MockChain {
+    pub tip_height: u32,
+    // scripthash -> [(txid, height)], height 0 for mempool
+    histories: HashMap<String, Vec<(Txid, i32)>>,
+    // scripthash -> (confirmed, unconfirmed)
+    balances: HashMap<String, (u64, i64)>,
+    transactions: HashMap<Txid, Transaction>,
+    broadcasted: Vec<Transaction>
+}
+
+impl MockChain {
+
+    pub fn new(tip_height: u32) -> Self {
+        MockChain {
+            tip_height,
+            ..MockChain::default()
+        }
+    }
+
+    // records `tx` as touching `script` at `height` (0 for mempool)
+    pub fn add_history(&mut self, script: &Script, tx: &Transaction, height: i32) {
+        let txid = tx.txid();
+
+        self.histories.entry(script_hash(script)).or_default().push((txid, height));
+        self.transactions.insert(txid, tx.clone());
+    }
+
+    pub fn set_balance(&mut self, script: &Script, confirmed: u64, unconfirmed: i64) {
+        self.balances.insert(script_hash(script), (confirmed, unconfirmed));
+    }
+
+    pub fn broadcasted(&self) -> &[Transaction] {
+        &self.broadcasted
+    }
+
+    pub fn header(&self, height: u32) -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            prev_blockhash: BlockHash::default(),
+            merkle_root: TxMerkleNode::default(),
+            time: GENESIS_TIME + height * 600,
+            bits: 0,
+            nonce: height
+        }
+    }
+
+    // electrum status: sha256 of "txid:height:" for each history item, null without history
+    fn script_status(&self, script_hash: &str) -> Value {
+        match self.histories.get(script_hash) {
+            Some(history) if !history.is_empty() => {
+                let status: String = history.iter().map(|(txid, height)| format!("{}:{}:", txid, height)).collect();
+                json!(sha256::Hash::hash(status.as_bytes()).to_hex())
+            },
+            _ => Value::Null,
+        }
+    }
+
+    // outputs paying the script that no known transaction spends
+    fn list_unspent(&self, script_hash_hex: &str) -> Value {
+        let history = self.histories.get(script_hash_hex).cloned().unwrap_or_default();
+        let mut unspent = Vec::<Value>::new();
+
+        for (txid, height) in history {
+            let tx = match self.transactions.get(&txid) {
+                Some(tx) => tx,
+                None => continue,
+            };
+
+            for (vout, output) in tx.output.iter().enumerate() {
+                let outpoint = OutPoint::new(txid, vout as u32);
+                let spent = self.transactions.values().any(|tx| tx.input.iter().any(|input| input.previous_output == outpoint));
+
+                if script_hash(&output.script_pubkey) == script_hash_hex && !spent {
+                    unspent.push(json!({
+                        "tx_hash": txid.to_string(),
+                        "tx_pos": vout,
+                        "height": height,
+                        "value": output.value
+                    }));
+                }
+            }
+        }
+
+        json!(unspent)
+    }
+
+    fn handle(&mut self, method: &str, params: &[Value]) -> Result<Value, String> {
+        let param_str = |i: usize| params.get(i).and_then(Value::as_str).ok_or_else(|| format!("missing param {}", i));
+
+        match method {
+            "server.version" => Ok(json!(["rwallet-mock", "1.4"])),
+            "server.ping" => Ok(Value::Null),
+            "blockchain.relayfee" => Ok(json!(0.00001)),
+            "blockchain.estimatefee" => Ok(json!(0.0001)),
+            "blockchain.headers.subscribe" => Ok(json!({
+                "height": self.tip_height,
+                "hex": serialize_hex(&self.header(self.tip_height))
+            })),
+            "blockchain.block.header" => {
+                let height = params.get(0).and_then(Value::as_u64).ok_or("missing height")? as u32;
+
+                if height > self.tip_height {
+                    return Err(format!("height {} above tip", height));
+                }
+
+                Ok(json!(serialize_hex(&self.header(height))))
+            },
+            "blockchain.scripthash.get_history" => {
+                let history = self.histories.get(param_str(0)?).cloned().unwrap_or_default();
+
+                Ok(json!(history.iter().map(|(txid, height)| json!({
+                    "tx_hash": txid.to_string(),
+                    "height": height
+                })).collect::<Vec<Value>>()))
+            },
+            "blockchain.scripthash.get_balance" => {
+                let (confirmed, unconfirmed) = self.balances.get(param_str(0)?).cloned().unwrap_or((0, 0));
+
+                Ok(json!({ "confirmed": confirmed, "unconfirmed": unconfirmed }))
+            },
+            "blockchain.scripthash.subscribe" => Ok(self.script_status(param_str(0)?)),
+            "blockchain.scripthash.listunspent" => Ok(self.list_unspent(param_str(0)?)),
+            "blockchain.transaction.get" => {
+                let txid: Txid = param_str(0)?.parse().map_err(|_| "invalid txid")?;
+
+                self.transactions.get(&txid)
+                    .map(|tx| json!(serialize_hex(tx)))
+                    .ok_or_else(|| format!("unknown transaction {}", txid))
+            },
+            "blockchain.transaction.broadcast" => {
+                let bytes = Vec::<u8>::from_hex(param_str(0)?).map_err(|_| "invalid hex")?;
+                let tx: Transaction = deserialize(&bytes).map_err(|e| e.to_string())?;
+                let txid = tx.txid();
+
+                self.transactions.insert(txid, tx.clone());
+                self.broadcasted.push(tx);
+
+                Ok(json!(txid.to_string()))
+            },
+            _ => Err(format!("unknown method {}", method)),
+        }
+    }
+}
+
+// In-process Electrum JSON-RPC server over plain TCP. Each request line is
+// answered from the shared `MockChain`, which can be edited while serving.
+pub struct MockElectrumServer {
+    addr: SocketAddr,
+    chain: Arc<Mutex<MockChain>>
+}
+
+impl MockElectrumServer {
+
+    pub fn start(chain: MockChain) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let chain = Arc::new(Mutex::new(chain));
+
+        let server_chain = Arc::clone(&chain);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+
+                let connection_chain = Arc::clone(&server_chain);
+                thread::spawn(move || serve_connection(stream, connection_chain));
+            }
+        });
+
+        Ok(MockElectrumServer { addr, chain })
+    }
+
+    pub fn url(&self) -> String {
+        format!("tcp://{}", self.addr)
+    }
+
+    pub fn chain(&self) -> Arc<Mutex<MockChain>> {
+        Arc::clone(&self.chain)
+    }
+
+    pub fn connector(&self) -> Box<dyn ElectrumConnector> {
+        Box::new(UrlConnector::new(ServerSettings::new(&self.url())))
+    }
+}
+
+fn serve_connection(stream: TcpStream, chain: Arc<Mutex<MockChain>>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(_) => return,
+        };
+
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = request.get("params").and_then(Value::as_array).cloned().unwrap_or_default();
+
+        let response = match chain.lock().unwrap().handle(method, &params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(message) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32600, "message": message } }),
+        };
+
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+// electrum scripthash: sha256 of the script, hex encoded in reverse byte order
+pub fn script_hash(script: &Script) -> String {
+    let mut hash = sha256::Hash::hash(script.as_bytes()).into_inner();
+    hash.reverse();
+
+    hash.to_hex()
+}
@@ -0,0 +1,140 @@
+use iced::{button, text_input, Button, Checkbox, Column, Element, HorizontalAlignment, Length, Row, Text, TextInput, Align};
+
+use rwallet::format::{format_timestamp, BitcoinUnit};
+use rwallet::notifications::{Notification, NotificationHistory, NotificationSettings};
+use crate::theme::{ButtonStyle, CheckboxStyle, InputStyle, Palette};
+use crate::{RuWalletMessage, ROBOTO, ROBOTO_BOLD};
+
+// entries shown when the history is expanded
+const HISTORY_ROWS: usize = 50;
+
+#[derive(Debug, Default)]
+pub struct NotificationArea {
+    dismiss_button_state: button::State,
+
+    history_button_state: button::State,
+    clear_history_button_state: button::State,
+    pub show_history: bool,
+
+    thresholds_input_state: text_input::State,
+    pub thresholds_input_value: String,
+
+    save_settings_button_state: button::State
+}
+
+impl NotificationArea {
+
+    pub fn view<'a>(&'a mut self, toasts: &'a [Notification], history: &'a NotificationHistory, settings: &NotificationSettings, unit: BitcoinUnit, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let mut area = Column::new()
+            .spacing(10);
+
+        if !toasts.is_empty() {
+            let mut toast_list = Column::new()
+                .spacing(5)
+                .width(Length::Fill);
+
+            for toast in toasts {
+                toast_list = toast_list.push(
+                    Text::new(toast.describe(unit))
+                        .font(ROBOTO)
+                        .size(18)
+                        .color(palette.positive)
+                );
+            }
+
+            let dismiss_button = Button::new(&mut self.dismiss_button_state, Text::new("Dismiss"))
+                .padding(10)
+                .style(ButtonStyle(palette))
+                .on_press(RuWalletMessage::DismissNotifications);
+
+            area = area.push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(toast_list)
+                    .push(dismiss_button)
+            );
+        }
+
+        let history_label = if self.show_history { "Hide Notifications" } else { "Notification History" };
+
+        let history_button = Button::new(&mut self.history_button_state, Text::new(history_label))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::ToggleNotificationHistory);
+
+        if !self.show_history {
+            return area.push(history_button).into();
+        }
+
+        let history_title = Text::new("Notifications")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(25)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let clear_history_button = Button::new(&mut self.clear_history_button_state, Text::new("Clear"))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::ClearNotificationHistory);
+
+        let notify_incoming_checkbox = Checkbox::new(
+            settings.notify_incoming,
+            "Incoming payments",
+            RuWalletMessage::NotifyIncomingToggled
+        )
+        .text_size(20)
+        .font(ROBOTO)
+        .style(CheckboxStyle(palette));
+
+        let thresholds_input = TextInput::new(
+            &mut self.thresholds_input_state,
+            "Confirmations to announce (e.g. 1, 3, 6)",
+            &mut self.thresholds_input_value,
+            RuWalletMessage::NotificationThresholdsInputChanged
+        )
+        .padding(10)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let save_settings_button = Button::new(&mut self.save_settings_button_state, Text::new("Save"))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::SaveNotificationSettings);
+
+        let settings_row = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(notify_incoming_checkbox)
+            .push(thresholds_input)
+            .push(save_settings_button);
+
+        area = area
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(history_title)
+                    .push(clear_history_button)
+                    .push(history_button)
+            )
+            .push(settings_row);
+
+        if history.items.is_empty() {
+            area = area.push(Text::new("No notifications yet").font(ROBOTO).size(18).color(palette.muted));
+        }
+
+        for notification in history.items.iter().rev().take(HISTORY_ROWS) {
+            area = area.push(
+                Text::new(format!("{}  {}", format_timestamp(notification.timestamp), notification.describe(unit)))
+                    .font(ROBOTO)
+                    .size(16)
+            );
+        }
+
+        area.into()
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::format::BitcoinUnit;
+use crate::wallet_service::TransactionRow;
+
+// oldest entries are dropped past this size
+const MAX_HISTORY: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    pub notify_incoming: bool,
+    // confirmation counts announced for each transaction
+    pub confirmation_thresholds: Vec<u32>
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            notify_incoming: true,
+            confirmation_thresholds: vec![1, 3, 6]
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NotificationKind {
+    // `label` names the receiving address, e.g. "Receive #3"
+    Incoming { amount: u64, address: String, label: String },
+    Confirmed { confirmations: u32 }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    // unix time in seconds
+    pub timestamp: u64,
+    pub wallet_name: String,
+    pub txid: String,
+    pub kind: NotificationKind
+}
+
+impl Notification {
+    // toast and history text, amounts in the unit chosen in the settings
+    pub fn describe(&self, unit: BitcoinUnit) -> String {
+        match &self.kind {
+            NotificationKind::Incoming { amount, address, label } => {
+                format!("[{}] Received {} on {} ({})", self.wallet_name, unit.format_with_unit(*amount), label, address)
+            },
+            NotificationKind::Confirmed { confirmations } => {
+                format!("[{}] Transaction {} has {} confirmation{}", self.wallet_name, self.txid,
+                    confirmations, if *confirmations == 1 { "" } else { "s" })
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationHistory {
+    pub items: Vec<Notification>
+}
+
+impl NotificationHistory {
+
+    pub fn load() -> Result<Self, String> {
+        let path = history_path();
+
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid notification history {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(NotificationHistory::default()),
+            Err(e) => Err(format!("Cannot read notification history {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = history_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self).expect("history is always serializable");
+
+        fs::write(&path, content)
+    }
+
+    pub fn push(&mut self, notification: Notification) {
+        self.items.push(notification);
+
+        if self.items.len() > MAX_HISTORY {
+            let excess = self.items.len() - MAX_HISTORY;
+            self.items.drain(..excess);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+// confirmations of a transaction mined at `height` (0 while unconfirmed)
+pub fn confirmations(height: u32, tip_height: u32) -> u32 {
+    if height == 0 || height > tip_height {
+        0
+    } else {
+        tip_height - height + 1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionChange {
+    Incoming { txid: String, amount: u64 },
+    Confirmed { txid: String, confirmations: u32 }
+}
+
+// Compares two snapshots of the transaction list and returns the transactions
+// that just arrived and the confirmation thresholds that were just crossed.
+pub fn detect_changes(previous: &[TransactionRow], previous_tip: u32, current: &[TransactionRow], current_tip: u32, settings: &NotificationSettings) -> Vec<TransactionChange> {
+    let mut changes = Vec::<TransactionChange>::new();
+
+    for row in current {
+        let previous_row = previous.iter().find(|p| p.txid == row.txid);
+
+        if previous_row.is_none() && row.amount > 0 && settings.notify_incoming {
+            changes.push(TransactionChange::Incoming { txid: row.txid.clone(), amount: row.amount as u64 });
+        }
+
+        let before = previous_row.map(|p| confirmations(p.height, previous_tip)).unwrap_or(0);
+        let after = confirmations(row.height, current_tip);
+
+        for threshold in &settings.confirmation_thresholds {
+            if before < *threshold && *threshold <= after {
+                changes.push(TransactionChange::Confirmed { txid: row.txid.clone(), confirmations: *threshold });
+            }
+        }
+    }
+
+    changes
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn history_path() -> PathBuf {
+    config::data_dir().join("notifications.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(txid: &str, amount: i128, height: u32) -> TransactionRow {
+        TransactionRow {
+            txid: txid.to_string(),
+            amount,
+            height,
+            ..TransactionRow::default()
+        }
+    }
+
+    fn confirmed(txid: &str, confirmations: u32) -> TransactionChange {
+        TransactionChange::Confirmed { txid: txid.to_string(), confirmations }
+    }
+
+    #[test]
+    fn each_threshold_is_announced_once() {
+        let settings = NotificationSettings::default();
+        let pending = [row("a", 1000, 0)];
+        let mined = [row("a", 1000, 100)];
+
+        assert_eq!(detect_changes(&pending, 99, &mined, 100, &settings), vec![confirmed("a", 1)]);
+        assert_eq!(detect_changes(&mined, 100, &mined, 101, &settings), vec![]);
+        assert_eq!(detect_changes(&mined, 101, &mined, 102, &settings), vec![confirmed("a", 3)]);
+        assert_eq!(detect_changes(&mined, 102, &mined, 104, &settings), vec![]);
+        assert_eq!(detect_changes(&mined, 104, &mined, 105, &settings), vec![confirmed("a", 6)]);
+        assert_eq!(detect_changes(&mined, 105, &mined, 110, &settings), vec![]);
+    }
+
+    #[test]
+    fn catching_up_announces_every_skipped_threshold() {
+        let settings = NotificationSettings::default();
+
+        // offline while the transaction went from the mempool to 8 confirmations
+        let changes = detect_changes(&[row("a", 1000, 0)], 99, &[row("a", 1000, 100)], 107, &settings);
+
+        assert_eq!(changes, vec![confirmed("a", 1), confirmed("a", 3), confirmed("a", 6)]);
+
+        // only the thresholds past the last snapshot
+        let changes = detect_changes(&[row("a", 1000, 100)], 101, &[row("a", 1000, 100)], 107, &settings);
+
+        assert_eq!(changes, vec![confirmed("a", 3), confirmed("a", 6)]);
+    }
+
+    #[test]
+    fn only_new_incoming_transactions_are_announced() {
+        let settings = NotificationSettings::default();
+        let previous = [row("old", 1000, 0)];
+        let current = [row("old", 1000, 0), row("in", 2000, 0), row("out", -3000, 0)];
+
+        let changes = detect_changes(&previous, 100, &current, 100, &settings);
+
+        assert_eq!(changes, vec![TransactionChange::Incoming { txid: "in".to_string(), amount: 2000 }]);
+
+        let silent = NotificationSettings { notify_incoming: false, ..NotificationSettings::default() };
+
+        assert_eq!(detect_changes(&previous, 100, &current, 100, &silent), vec![]);
+    }
+
+    #[test]
+    fn custom_thresholds_are_followed() {
+        let settings = NotificationSettings { notify_incoming: false, confirmation_thresholds: vec![2] };
+        let pending = [row("a", 1000, 0)];
+        let mined = [row("a", 1000, 100)];
+
+        assert_eq!(detect_changes(&pending, 99, &mined, 100, &settings), vec![]);
+        assert_eq!(detect_changes(&mined, 100, &mined, 101, &settings), vec![confirmed("a", 2)]);
+    }
+}
@@ -0,0 +1,336 @@
+use iced::canvas::{self, Cache, Canvas, Cursor, Frame, Geometry, Path, Stroke};
+use iced::{button, Align, Button, Column, Element, HorizontalAlignment, Length, Point, Rectangle, Row, Size, Text, VerticalAlignment};
+
+use rwallet::format::{format_timestamp, BitcoinUnit};
+use rwallet::TransactionRow;
+use crate::theme::{ButtonStyle, Palette, LIGHT};
+use crate::{RuWalletMessage, ROBOTO, ROBOTO_BOLD};
+
+const DAY: u64 = 86_400;
+
+// room for the axis labels
+const LEFT_MARGIN: f32 = 150.0;
+const BOTTOM_MARGIN: f32 = 30.0;
+const TOP_MARGIN: f32 = 10.0;
+const RIGHT_MARGIN: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartRange {
+    Month,
+    Year,
+    All
+}
+
+impl Default for ChartRange {
+    fn default() -> Self {
+        ChartRange::All
+    }
+}
+
+impl ChartRange {
+    // seconds shown before now, None for the whole history
+    fn duration(self) -> Option<u64> {
+        match self {
+            ChartRange::Month => Some(30 * DAY),
+            ChartRange::Year => Some(365 * DAY),
+            ChartRange::All => None,
+        }
+    }
+}
+
+// Balance of the wallet over time, drawn from the transaction history.
+#[derive(Debug, Default)]
+pub struct OverviewView {
+    month_button_state: button::State,
+    year_button_state: button::State,
+    all_button_state: button::State,
+
+    chart: BalanceChart
+}
+
+impl OverviewView {
+
+    pub fn select_range(&mut self, range: ChartRange) {
+        self.chart.range = range;
+        self.chart.cache.clear();
+    }
+
+    pub fn view<'a>(&'a mut self, transactions: &[TransactionRow], unit: BitcoinUnit, now: u64, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        self.chart.update(balance_history(transactions, now), unit, now, palette);
+
+        let chart_title = Text::new("Balance History")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let range = self.chart.range;
+
+        let range_button = |state, label, button_range: ChartRange| {
+            let button = Button::new(state, Text::new(label)).padding(10).style(ButtonStyle(palette));
+
+            if button_range == range {
+                button
+            } else {
+                button.on_press(RuWalletMessage::ChartRangeSelected(button_range))
+            }
+        };
+
+        let header = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(chart_title)
+            .push(range_button(&mut self.month_button_state, "1M", ChartRange::Month))
+            .push(range_button(&mut self.year_button_state, "1Y", ChartRange::Year))
+            .push(range_button(&mut self.all_button_state, "All", ChartRange::All));
+
+        let mut overview = Column::new()
+            .spacing(20)
+            .push(header);
+
+        if self.chart.points.is_empty() {
+            return overview
+                .push(
+                    Text::new("No transactions yet")
+                        .font(ROBOTO)
+                        .size(20)
+                        .color(palette.muted)
+                )
+                .into();
+        }
+
+        overview = overview.push(
+            Canvas::new(&mut self.chart)
+                .width(Length::Fill)
+                .height(Length::Units(400))
+        );
+
+        overview.into()
+    }
+}
+
+#[derive(Debug, Default)]
+struct BalanceChart {
+    // the line and the axes, redrawn only when the data changes
+    cache: Cache,
+
+    points: Vec<(u64, i128)>,
+    unit: Option<BitcoinUnit>,
+    palette: Option<Palette>,
+    now: u64,
+    range: ChartRange
+}
+
+impl BalanceChart {
+
+    fn update(&mut self, points: Vec<(u64, i128)>, unit: BitcoinUnit, now: u64, palette: Palette) {
+        // the end of the axis moves with the clock, a day at a time is enough
+        let now = now - now % DAY + DAY;
+
+        if points != self.points || Some(unit) != self.unit || Some(palette) != self.palette || now != self.now {
+            self.points = points;
+            self.unit = Some(unit);
+            self.palette = Some(palette);
+            self.now = now;
+            self.cache.clear();
+        }
+    }
+
+    // first and last second of the x axis
+    fn time_range(&self) -> (u64, u64) {
+        let first = self.points.first().map(|(time, _)| *time).unwrap_or(self.now);
+
+        let start = match self.range.duration() {
+            Some(duration) => self.now.saturating_sub(duration),
+            None => first.min(self.now.saturating_sub(DAY)),
+        };
+
+        (start, self.now)
+    }
+
+    // balance at `time`, before the first transaction it is zero
+    fn balance_at(&self, time: u64) -> i128 {
+        self.points.iter()
+            .take_while(|(point_time, _)| *point_time <= time)
+            .last()
+            .map(|(_, balance)| *balance)
+            .unwrap_or(0)
+    }
+
+    fn max_balance(&self, start: u64) -> i128 {
+        let visible = self.points.iter()
+            .filter(|(time, _)| *time >= start)
+            .map(|(_, balance)| *balance);
+
+        visible.chain(std::iter::once(self.balance_at(start))).max().unwrap_or(0).max(1)
+    }
+
+    fn plot_area(bounds: Size) -> Rectangle {
+        Rectangle {
+            x: LEFT_MARGIN,
+            y: TOP_MARGIN,
+            width: (bounds.width - LEFT_MARGIN - RIGHT_MARGIN).max(1.0),
+            height: (bounds.height - TOP_MARGIN - BOTTOM_MARGIN).max(1.0)
+        }
+    }
+}
+
+impl canvas::Program<RuWalletMessage> for BalanceChart {
+
+    fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+        let unit = self.unit.unwrap_or_default();
+        let palette = self.palette.unwrap_or(LIGHT);
+        let (start, end) = self.time_range();
+        let max_balance = self.max_balance(start);
+        let area = Self::plot_area(bounds.size());
+
+        let x_of = |time: u64| area.x + area.width * (time.saturating_sub(start)) as f32 / (end - start) as f32;
+        let y_of = |balance: i128| area.y + area.height - area.height * balance.max(0) as f32 / max_balance as f32;
+
+        let chart = self.cache.draw(bounds.size(), |frame| {
+            let axes = Path::new(|builder| {
+                builder.move_to(Point::new(area.x, area.y));
+                builder.line_to(Point::new(area.x, area.y + area.height));
+                builder.line_to(Point::new(area.x + area.width, area.y + area.height));
+            });
+
+            frame.stroke(&axes, Stroke { color: palette.muted, width: 1.0, ..Stroke::default() });
+
+            // the balance only changes with a transaction, so the line is drawn in steps
+            let line = Path::new(|builder| {
+                let mut balance = self.balance_at(start);
+
+                builder.move_to(Point::new(x_of(start), y_of(balance)));
+
+                for (time, new_balance) in self.points.iter().filter(|(time, _)| *time > start) {
+                    builder.line_to(Point::new(x_of(*time), y_of(balance)));
+                    builder.line_to(Point::new(x_of(*time), y_of(*new_balance)));
+                    balance = *new_balance;
+                }
+
+                builder.line_to(Point::new(x_of(end), y_of(balance)));
+            });
+
+            frame.stroke(&line, Stroke { color: palette.accent, width: 2.0, ..Stroke::default() });
+
+            let labels = [
+                (unit.format_with_unit(max_balance as u64), Point::new(area.x - 10.0, area.y), HorizontalAlignment::Right, VerticalAlignment::Top),
+                (unit.format_with_unit(0), Point::new(area.x - 10.0, area.y + area.height), HorizontalAlignment::Right, VerticalAlignment::Bottom),
+                (format_timestamp(start)[..10].to_string(), Point::new(area.x, area.y + area.height + 5.0), HorizontalAlignment::Left, VerticalAlignment::Top),
+                (format_timestamp(end)[..10].to_string(), Point::new(area.x + area.width, area.y + area.height + 5.0), HorizontalAlignment::Right, VerticalAlignment::Top),
+            ];
+
+            for (content, position, horizontal_alignment, vertical_alignment) in labels.iter().cloned() {
+                frame.fill_text(canvas::Text {
+                    content,
+                    position,
+                    color: palette.muted,
+                    size: 16.0,
+                    font: ROBOTO,
+                    horizontal_alignment,
+                    vertical_alignment
+                });
+            }
+        });
+
+        // the tooltip follows the cursor, so it isn't cached
+        let mut tooltip = Frame::new(bounds.size());
+
+        if let Some(position) = cursor.position_in(&bounds) {
+            if area.contains(position) {
+                let time = start + ((position.x - area.x) / area.width * (end - start) as f32) as u64;
+                let balance = self.balance_at(time);
+                let point = Point::new(position.x, y_of(balance));
+
+                tooltip.stroke(
+                    &Path::line(Point::new(position.x, area.y), Point::new(position.x, area.y + area.height)),
+                    Stroke { color: palette.muted, width: 1.0, ..Stroke::default() }
+                );
+
+                tooltip.fill(&Path::circle(point, 4.0), palette.accent);
+
+                let label = format!("{}  {}", &format_timestamp(time)[..10], unit.format_with_unit(balance.max(0) as u64));
+
+                // keep the box inside the chart near the right edge
+                let box_size = Size::new(9.0 * label.len() as f32, 26.0);
+                let box_x = if point.x + 10.0 + box_size.width > bounds.width { point.x - 10.0 - box_size.width } else { point.x + 10.0 };
+                let box_y = (point.y - box_size.height - 10.0).max(0.0);
+
+                tooltip.fill(&Path::rectangle(Point::new(box_x, box_y), box_size), palette.surface);
+                tooltip.fill_text(canvas::Text {
+                    content: label,
+                    position: Point::new(box_x + 6.0, box_y + 5.0),
+                    color: palette.text,
+                    size: 16.0,
+                    font: ROBOTO,
+                    ..canvas::Text::default()
+                });
+            }
+        }
+
+        vec![chart, tooltip.into_geometry()]
+    }
+}
+
+// Running balance after each transaction, oldest first: (block time, balance).
+// Unconfirmed transactions are counted at `now`; within one block deposits come
+// first, so the balance never dips below zero between them.
+fn balance_history(transactions: &[TransactionRow], now: u64) -> Vec<(u64, i128)> {
+    let mut changes: Vec<(u64, i128)> = transactions.iter()
+        .map(|tx| (tx.timestamp.unwrap_or(now), tx.amount))
+        .collect();
+
+    changes.sort_by_key(|(timestamp, amount)| (*timestamp, std::cmp::Reverse(*amount)));
+
+    let mut balance = 0;
+
+    changes.into_iter()
+        .map(|(timestamp, amount)| {
+            balance += amount;
+            (timestamp, balance)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(amount: i128, timestamp: Option<u64>) -> TransactionRow {
+        TransactionRow {
+            amount,
+            timestamp,
+            ..TransactionRow::default()
+        }
+    }
+
+    #[test]
+    fn balance_history_is_oldest_first() {
+        // newest first, like `WalletService::transactions`
+        let transactions = [row(-30, Some(300)), row(50, Some(200)), row(100, Some(100))];
+
+        assert_eq!(balance_history(&transactions, 1000), vec![(100, 100), (200, 150), (300, 120)]);
+    }
+
+    #[test]
+    fn balance_history_counts_unconfirmed_at_now() {
+        let transactions = [row(5, None), row(100, Some(100))];
+
+        assert_eq!(balance_history(&transactions, 1000), vec![(100, 100), (1000, 105)]);
+    }
+
+    #[test]
+    fn balance_history_puts_deposits_first_within_a_block() {
+        // the coins received are spent again in the same block
+        let transactions = [row(-80, Some(100)), row(100, Some(100))];
+
+        assert_eq!(balance_history(&transactions, 1000), vec![(100, 100), (100, 20)]);
+    }
+
+    #[test]
+    fn balance_history_is_empty_without_transactions() {
+        assert!(balance_history(&[], 1000).is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::Path;
+
+use crate::format::{parse_date, SATS_PER_BTC};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiatSettings {
+    // shown next to the values, the price file is expected to be in this currency
+    pub currency: String,
+    // CSV or JSON file of historical BTC prices, empty to hide fiat values
+    pub price_file: String
+}
+
+impl Default for FiatSettings {
+    fn default() -> Self {
+        FiatSettings {
+            currency: "USD".to_string(),
+            price_file: String::new()
+        }
+    }
+}
+
+// one entry of a JSON price file, e.g. {"date": "2021-01-31", "price": 33114.36}
+// or {"timestamp": 1612051200, "price": 33114.36}
+#[derive(Debug, Deserialize)]
+struct PriceEntry {
+    #[serde(alias = "timestamp", alias = "time")]
+    date: PriceTime,
+    price: f64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PriceTime {
+    Unix(u64),
+    Date(String)
+}
+
+// Historical BTC prices loaded from a local file, so valuations work offline.
+#[derive(Debug, Clone, Default)]
+pub struct PriceHistory {
+    // (unix time in seconds, price of one BTC), sorted by time
+    prices: Vec<(u64, f64)>
+}
+
+impl PriceHistory {
+
+    // picks the format from the extension, anything but .json is read as CSV
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read price file {}: {}", path.display(), e))?;
+
+        let is_json = path.extension().map(|extension| extension.eq_ignore_ascii_case("json")).unwrap_or(false);
+
+        let history = if is_json { Self::from_json(&content)? } else { Self::from_csv(&content)? };
+
+        if history.prices.is_empty() {
+            return Err(format!("No prices in {}", path.display()));
+        }
+
+        Ok(history)
+    }
+
+    // "date,price" lines where the date is YYYY-MM-DD or a unix timestamp; a header line is skipped
+    pub fn from_csv(content: &str) -> Result<Self, String> {
+        let mut prices = Vec::<(u64, f64)>::new();
+
+        for (n, line) in content.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim().trim_matches('"')).collect();
+
+            if fields.len() < 2 {
+                return Err(format!("Line {} of the price file needs a date and a price", n + 1));
+            }
+
+            let price = match fields[1].parse::<f64>() {
+                Ok(price) => price,
+                Err(_) if prices.is_empty() && n == 0 => continue,
+                Err(_) => return Err(format!("Invalid price '{}' on line {}", fields[1], n + 1)),
+            };
+
+            let price = check_price(price).map_err(|e| format!("{} on line {}", e, n + 1))?;
+
+            prices.push((parse_time(fields[0]).map_err(|e| format!("{} on line {}", e, n + 1))?, price));
+        }
+
+        Ok(Self::from_prices(prices))
+    }
+
+    // array of {"date": ..., "price": ...} objects
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let entries: Vec<PriceEntry> = serde_json::from_str(content)
+            .map_err(|e| format!("Invalid price file: {}", e))?;
+
+        let mut prices = Vec::<(u64, f64)>::new();
+
+        for entry in entries {
+            let time = match entry.date {
+                PriceTime::Unix(timestamp) => timestamp,
+                PriceTime::Date(date) => parse_time(&date)?,
+            };
+
+            prices.push((time, check_price(entry.price)?));
+        }
+
+        Ok(Self::from_prices(prices))
+    }
+
+    fn from_prices(mut prices: Vec<(u64, f64)>) -> Self {
+        prices.sort_by_key(|(time, _)| *time);
+
+        PriceHistory {
+            prices
+        }
+    }
+
+    // last known price at `timestamp`, None before the first one
+    pub fn price_at(&self, timestamp: u64) -> Option<f64> {
+        match self.prices.binary_search_by_key(&timestamp, |(time, _)| *time) {
+            Ok(n) => Some(self.prices[n].1),
+            Err(0) => None,
+            Err(n) => Some(self.prices[n - 1].1),
+        }
+    }
+
+    pub fn latest_price(&self) -> Option<f64> {
+        self.prices.last().map(|(_, price)| *price)
+    }
+
+    // value of `sats` at `timestamp`, unconfirmed amounts (None) use the latest price
+    pub fn value(&self, sats: i128, timestamp: Option<u64>) -> Option<f64> {
+        let price = match timestamp {
+            Some(timestamp) => self.price_at(timestamp)?,
+            None => self.latest_price()?,
+        };
+
+        Some(sats as f64 / SATS_PER_BTC as f64 * price)
+    }
+}
+
+// "NaN" and "inf" parse as floats, but no valuation can be made from them
+fn check_price(price: f64) -> Result<f64, String> {
+    if price.is_finite() && price >= 0.0 {
+        Ok(price)
+    } else {
+        Err(format!("Invalid price '{}'", price))
+    }
+}
+
+fn parse_time(value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(timestamp) => Ok(timestamp),
+        Err(_) => parse_date(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+
+    // 2021-01-01 and 2021-01-03
+    const JAN_1: u64 = 1_609_459_200;
+    const JAN_3: u64 = JAN_1 + 2 * DAY;
+
+    #[test]
+    fn csv_accepts_dates_timestamps_and_a_header() {
+        let history = PriceHistory::from_csv("date,price\n2021-01-03,\"32000.5\"\n\n# comment\n1609459200, 29000\n").unwrap();
+
+        assert_eq!(history.prices, vec![(JAN_1, 29000.0), (JAN_3, 32000.5)]);
+    }
+
+    #[test]
+    fn csv_header_is_only_skipped_on_the_first_line() {
+        assert!(PriceHistory::from_csv("2021-01-01,29000\ndate,price").is_err());
+        assert!(PriceHistory::from_csv("2021-01-01").is_err());
+        assert!(PriceHistory::from_csv("2021-02-31,29000").is_err());
+    }
+
+    #[test]
+    fn json_accepts_dates_and_timestamps() {
+        let history = PriceHistory::from_json(r#"[
+            {"date": "2021-01-03", "price": 32000.5},
+            {"timestamp": 1609459200, "price": 29000},
+            {"time": 1609545600, "price": 30000}
+        ]"#).unwrap();
+
+        assert_eq!(history.prices, vec![(JAN_1, 29000.0), (JAN_1 + DAY, 30000.0), (JAN_3, 32000.5)]);
+        assert!(PriceHistory::from_json(r#"{"date": "2021-01-03", "price": 1}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_nan_and_negative_prices() {
+        assert!(PriceHistory::from_csv("2021-01-01,NaN").is_err());
+        assert!(PriceHistory::from_csv("2021-01-01,inf").is_err());
+        assert!(PriceHistory::from_csv("2021-01-01,-1").is_err());
+        assert!(PriceHistory::from_json(r#"[{"date": "2021-01-01", "price": -29000}]"#).is_err());
+        assert!(PriceHistory::from_csv("2021-01-01,0").is_ok());
+    }
+
+    #[test]
+    fn price_at_uses_the_last_known_price() {
+        let history = PriceHistory::from_csv("2021-01-01,29000\n2021-01-03,32000").unwrap();
+
+        assert_eq!(history.price_at(JAN_1 - 1), None);
+        assert_eq!(history.price_at(JAN_1), Some(29000.0));
+        assert_eq!(history.price_at(JAN_1 + DAY), Some(29000.0));
+        assert_eq!(history.price_at(JAN_3), Some(32000.0));
+        assert_eq!(history.price_at(JAN_3 + 365 * DAY), Some(32000.0));
+        assert_eq!(history.latest_price(), Some(32000.0));
+    }
+
+    #[test]
+    fn values_confirmed_amounts_at_their_time_and_pending_ones_at_the_latest_price() {
+        let history = PriceHistory::from_csv("2021-01-01,20000\n2021-01-03,30000").unwrap();
+
+        assert_eq!(history.value(SATS_PER_BTC as i128 / 2, Some(JAN_1 + DAY)), Some(10000.0));
+        assert_eq!(history.value(-(SATS_PER_BTC as i128), None), Some(-30000.0));
+        assert_eq!(history.value(SATS_PER_BTC as i128, Some(JAN_1 - DAY)), None);
+        assert_eq!(PriceHistory::default().value(1, None), None);
+    }
+}
@@ -0,0 +1,238 @@
+use iced::{button, qr_code, text_input, Align, Button, Column, Element, HorizontalAlignment, Length, QRCode, Row, Text, TextInput};
+
+use rwallet::bip21::PaymentUri;
+use rwallet::format::BitcoinUnit;
+use crate::theme::{ButtonStyle, InputStyle, Palette};
+use crate::{RuWalletMessage, ROBOTO, ROBOTO_BOLD};
+
+const MIN_CELL_SIZE: u16 = 2;
+const MAX_CELL_SIZE: u16 = 16;
+const DEFAULT_CELL_SIZE: u16 = 6;
+
+// Receive address with the optional BIP21 fields and a QR code of the result.
+#[derive(Debug)]
+pub struct ReceiveView {
+    amount_input_state: text_input::State,
+    pub amount_input_value: String,
+
+    label_input_state: text_input::State,
+    pub label_input_value: String,
+
+    message_input_state: text_input::State,
+    pub message_input_value: String,
+
+    zoom_in_button_state: button::State,
+    zoom_out_button_state: button::State,
+    cell_size: u16,
+
+    copy_address_button_state: button::State,
+    copy_uri_button_state: button::State,
+
+    address: String,
+    uri: String,
+    qr_code: Option<qr_code::State>,
+    error: Option<String>
+}
+
+impl Default for ReceiveView {
+    fn default() -> Self {
+        ReceiveView {
+            amount_input_state: text_input::State::default(),
+            amount_input_value: String::new(),
+            label_input_state: text_input::State::default(),
+            label_input_value: String::new(),
+            message_input_state: text_input::State::default(),
+            message_input_value: String::new(),
+            zoom_in_button_state: button::State::default(),
+            zoom_out_button_state: button::State::default(),
+            cell_size: DEFAULT_CELL_SIZE,
+            copy_address_button_state: button::State::default(),
+            copy_uri_button_state: button::State::default(),
+            address: String::new(),
+            uri: String::new(),
+            qr_code: None,
+            error: None
+        }
+    }
+}
+
+impl ReceiveView {
+
+    // rebuilds the URI and its QR code, call whenever the address, a field or the unit changes
+    pub fn update(&mut self, address: &str, unit: BitcoinUnit) {
+        self.error = None;
+        self.address = address.to_string();
+
+        let amount = match self.amount_input_value.trim() {
+            "" => None,
+            value => match unit.parse(value) {
+                Ok(amount) => Some(amount),
+                Err(e) => {
+                    self.error = Some(e);
+                    None
+                },
+            },
+        };
+
+        let payment_uri = PaymentUri {
+            address: address.to_string(),
+            amount,
+            label: Some(self.label_input_value.trim().to_string()),
+            message: Some(self.message_input_value.trim().to_string())
+        };
+
+        self.uri = payment_uri.to_uri();
+
+        // uppercase bech32 fits the QR alphanumeric mode and gives a smaller code
+        let qr_data = if self.uri == address && address.starts_with(|c| c == 'b' || c == 't') {
+            address.to_uppercase()
+        } else {
+            self.uri.clone()
+        };
+
+        self.qr_code = match qr_code::State::new(qr_data) {
+            Ok(qr_code) => Some(qr_code),
+            Err(e) => {
+                self.error = Some(format!("Cannot encode QR code: {:?}", e));
+                None
+            },
+        };
+    }
+
+    pub fn zoom(&mut self, zoom_in: bool) {
+        self.cell_size = if zoom_in {
+            (self.cell_size + 1).min(MAX_CELL_SIZE)
+        } else {
+            (self.cell_size - 1).max(MIN_CELL_SIZE)
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.amount_input_value.clear();
+        self.label_input_value.clear();
+        self.message_input_value.clear();
+        self.address.clear();
+        self.uri.clear();
+        self.qr_code = None;
+        self.error = None;
+    }
+
+    pub fn view(&mut self, unit: BitcoinUnit, palette: Palette) -> Element<RuWalletMessage> {
+
+        let new_address_title = Text::new("Current Receive Address")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let amount_input = TextInput::new(
+            &mut self.amount_input_state,
+            &format!("Amount ({})", unit),
+            &mut self.amount_input_value,
+            RuWalletMessage::ReceiveAmountInputChanged
+        )
+        .width(Length::Units(200))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let label_input = TextInput::new(
+            &mut self.label_input_state,
+            "Label",
+            &mut self.label_input_value,
+            RuWalletMessage::ReceiveLabelInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let message_input = TextInput::new(
+            &mut self.message_input_state,
+            "Message",
+            &mut self.message_input_value,
+            RuWalletMessage::ReceiveMessageInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let request_fields = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(amount_input)
+            .push(label_input)
+            .push(message_input);
+
+        let uri_text = Text::new(&self.uri)
+            .font(ROBOTO)
+            .width(Length::Fill)
+            .size(20)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let copy_address_button = Button::new(&mut self.copy_address_button_state, Text::new("Copy Address"))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::Copy(self.address.clone()));
+
+        let mut uri_row = Row::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(uri_text)
+            .push(copy_address_button);
+
+        if self.uri != self.address {
+            uri_row = uri_row.push(
+                Button::new(&mut self.copy_uri_button_state, Text::new("Copy URI"))
+                    .padding(10)
+                    .style(ButtonStyle(palette))
+                    .on_press(RuWalletMessage::Copy(self.uri.clone()))
+            );
+        }
+
+        let mut receive = Column::new()
+            .spacing(20)
+            .push(new_address_title)
+            .push(uri_row)
+            .push(request_fields);
+
+        if let Some(error) = &self.error {
+            receive = receive.push(
+                Text::new(error)
+                    .font(ROBOTO)
+                    .size(20)
+                    .color(palette.negative)
+            );
+        }
+
+        if let Some(qr_code) = &mut self.qr_code {
+            let zoom_buttons = Column::new()
+                .spacing(10)
+                .push(
+                    Button::new(&mut self.zoom_in_button_state, Text::new("+"))
+                        .padding(10)
+                        .style(ButtonStyle(palette))
+                        .on_press(RuWalletMessage::ZoomQrCode(true))
+                )
+                .push(
+                    Button::new(&mut self.zoom_out_button_state, Text::new("-"))
+                        .padding(10)
+                        .style(ButtonStyle(palette))
+                        .on_press(RuWalletMessage::ZoomQrCode(false))
+                );
+
+            receive = receive.push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Start)
+                    .push(QRCode::new(qr_code).cell_size(self.cell_size))
+                    .push(zoom_buttons)
+            );
+        }
+
+        receive.into()
+    }
+}
@@ -0,0 +1,173 @@
+use bdk::bitcoin::{Address, Network};
+use iced::{button, text_input, Align, Button, Column, Element, HorizontalAlignment, Length, Row, Text, TextInput};
+
+use rwallet::bip21::PaymentUri;
+use rwallet::format::BitcoinUnit;
+use crate::theme::{ButtonStyle, InputStyle, Palette};
+use crate::{RuWalletMessage, ROBOTO, ROBOTO_BOLD};
+
+use std::str::FromStr;
+
+#[derive(Debug, Default)]
+pub struct SendView {
+    recipient_input_state: text_input::State,
+    pub recipient_input_value: String,
+
+    amount_input_state: text_input::State,
+    pub amount_input_value: String,
+
+    fee_rate_input_state: text_input::State,
+    pub fee_rate_input_value: String,
+
+    send_button_state: button::State,
+
+    // from the payment URI
+    label: Option<String>,
+    message: Option<String>,
+
+    pub warnings: Vec<String>,
+
+    // txid of the broadcast transaction, or the PSBT still missing signatures
+    pub status: String
+}
+
+impl SendView {
+
+    // fills in the form from a pasted `bitcoin:` URI and checks the address network
+    pub fn recipient_changed(&mut self, value: String, network: Network, unit: BitcoinUnit) {
+        self.warnings.clear();
+        self.status.clear();
+        self.label = None;
+        self.message = None;
+
+        match PaymentUri::parse(&value) {
+            Ok(payment_uri) => {
+                // only expand a URI once it holds a complete address, so typing one by hand still works
+                if payment_uri.address != value.trim() && Address::from_str(&payment_uri.address).is_ok() {
+                    if let Some(amount) = payment_uri.amount {
+                        self.amount_input_value = unit.format(amount);
+                    }
+                    self.label = payment_uri.label.clone();
+                    self.message = payment_uri.message.clone();
+                    self.recipient_input_value = payment_uri.address.clone();
+                } else {
+                    self.recipient_input_value = value;
+                }
+
+                if !self.recipient_input_value.is_empty() {
+                    self.warnings.extend(payment_uri.network_warning(network));
+                }
+            },
+            Err(e) => {
+                self.recipient_input_value = value;
+                self.warnings.push(e);
+            },
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.recipient_input_value.clear();
+        self.amount_input_value.clear();
+        self.fee_rate_input_value.clear();
+        self.label = None;
+        self.message = None;
+        self.warnings.clear();
+        self.status.clear();
+    }
+
+    pub fn view(&mut self, unit: BitcoinUnit, palette: Palette) -> Element<RuWalletMessage> {
+
+        let send_title = Text::new("Send")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let recipient_input = TextInput::new(
+            &mut self.recipient_input_state,
+            "Recipient Address or bitcoin: URI",
+            &mut self.recipient_input_value,
+            RuWalletMessage::SendRecipientInputChanged
+        )
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let amount_input = TextInput::new(
+            &mut self.amount_input_state,
+            &format!("Amount ({})", unit),
+            &mut self.amount_input_value,
+            RuWalletMessage::SendAmountInputChanged
+        )
+        .width(Length::Units(200))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let fee_rate_input = TextInput::new(
+            &mut self.fee_rate_input_state,
+            "Fee Rate (sat/vB)",
+            &mut self.fee_rate_input_value,
+            RuWalletMessage::SendFeeRateInputChanged
+        )
+        .width(Length::Units(200))
+        .padding(15)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let mut send_button = Button::new(&mut self.send_button_state, Text::new("Send"))
+            .padding(10)
+            .style(ButtonStyle(palette));
+
+        // a URI we can't fully honour must not be paid
+        if self.warnings.is_empty() {
+            send_button = send_button.on_press(RuWalletMessage::Send);
+        }
+
+        let mut send = Column::new()
+            .spacing(20)
+            .push(send_title)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(recipient_input)
+                    .push(amount_input)
+                    .push(fee_rate_input)
+                    .push(send_button)
+            );
+
+        if let Some(label) = &self.label {
+            send = send.push(Text::new(format!("Label: {}", label)).font(ROBOTO).size(20));
+        }
+
+        if let Some(message) = &self.message {
+            send = send.push(Text::new(format!("Message: {}", message)).font(ROBOTO).size(20));
+        }
+
+        for warning in &self.warnings {
+            send = send.push(
+                Text::new(warning)
+                    .font(ROBOTO)
+                    .width(Length::Fill)
+                    .size(20)
+                    .color(palette.warning)
+            );
+        }
+
+        if !self.status.is_empty() {
+            send = send.push(
+                Text::new(&self.status)
+                    .font(ROBOTO)
+                    .width(Length::Fill)
+                    .size(20)
+            );
+        }
+
+        send.into()
+    }
+}
@@ -0,0 +1,351 @@
+use iced::{button, text_input, Align, Button, Color, Column, Element, HorizontalAlignment, Length, Row, Text, TextInput};
+
+use crate::theme::{ButtonStyle, HeaderStyle, InputStyle, Palette, RowStyle};
+use crate::{RuWalletMessage, Table, ROBOTO, ROBOTO_BOLD};
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+// rows built per page, whatever the size of the wallet
+pub const PAGE_SIZE: usize = 50;
+
+// One column of a `DataTable`: how to display, colour and sort a row of type `T`.
+pub struct TableColumn<'a, T> {
+    title: String,
+    width: u16,
+    alignment: HorizontalAlignment,
+    format: Box<dyn Fn(&T) -> String + 'a>,
+    color: Option<Box<dyn Fn(&T) -> Color + 'a>>,
+    // columns without a comparison cannot be sorted on
+    compare: Option<Box<dyn Fn(&T, &T) -> Ordering + 'a>>
+}
+
+impl<'a, T> TableColumn<'a, T> {
+    pub fn new<F>(title: &str, width: u16, alignment: HorizontalAlignment, format: F) -> Self
+    where
+        F: Fn(&T) -> String + 'a
+    {
+        TableColumn {
+            title: title.to_string(),
+            width,
+            alignment,
+            format: Box::new(format),
+            color: None,
+            compare: None
+        }
+    }
+
+    pub fn color<F>(mut self, color: F) -> Self
+    where
+        F: Fn(&T) -> Color + 'a
+    {
+        self.color = Some(Box::new(color));
+        self
+    }
+
+    pub fn sort<F>(mut self, compare: F) -> Self
+    where
+        F: Fn(&T, &T) -> Ordering + 'a
+    {
+        self.compare = Some(Box::new(compare));
+        self
+    }
+}
+
+// Generic list with search, sortable headers, paging, a selected row and a
+// copy button per row. Every list of the wallet renders through it.
+#[derive(Debug, Default)]
+pub struct DataTable {
+    // empty hides the search box
+    search_placeholder: String,
+
+    search_input_state: text_input::State,
+    search: String,
+
+    header_button_states: Vec<button::State>,
+
+    // None keeps the order the rows were given in
+    sort_column: Option<usize>,
+    ascending: bool,
+
+    // zero based
+    page: usize,
+
+    previous_page_button_state: button::State,
+    next_page_button_state: button::State,
+
+    page_input_state: text_input::State,
+    page_input_value: String,
+    jump_button_state: button::State,
+
+    // key of the selected row
+    selected: Option<String>,
+
+    row_button_states: Vec<button::State>,
+    copy_button_states: Vec<button::State>
+}
+
+impl DataTable {
+
+    pub fn searchable(placeholder: &str) -> Self {
+        DataTable {
+            search_placeholder: placeholder.to_string(),
+            ..DataTable::default()
+        }
+    }
+
+    // clicking the sorted column again flips the direction
+    pub fn sort_by(&mut self, column: usize) {
+        if self.sort_column == Some(column) {
+            self.ascending = !self.ascending;
+        } else {
+            self.sort_column = Some(column);
+            self.ascending = true;
+        }
+
+        self.page = 0;
+    }
+
+    pub fn set_search(&mut self, search: String) {
+        self.search = search;
+        self.page = 0;
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        self.page = page;
+    }
+
+    pub fn set_page_input(&mut self, value: String) {
+        self.page_input_value = value;
+    }
+
+    // jumps to the one based page typed by the user
+    pub fn jump_to_page(&mut self) {
+        if let Ok(page) = self.page_input_value.trim().parse::<usize>() {
+            self.page = page.saturating_sub(1);
+        }
+
+        self.page_input_value.clear();
+    }
+
+    pub fn first_page(&mut self) {
+        self.page = 0;
+    }
+
+    pub fn select(&mut self, key: &str) {
+        self.selected = Some(key.to_string());
+    }
+
+    // keeps the page within the rows left after filtering and returns the rows to build
+    fn page_range(&mut self, total: usize) -> Range<usize> {
+        let pages = page_count(total);
+
+        if self.page >= pages {
+            self.page = pages - 1;
+        }
+
+        let start = self.page * PAGE_SIZE;
+
+        start..(start + PAGE_SIZE).min(total)
+    }
+
+    // Searches, sorts and pages `rows`, then builds the visible ones. `key`
+    // identifies a row for the selection and is what its copy button copies,
+    // `toolbar` is shown next to the search box.
+    pub fn view<'a, T>(&'a mut self, table: Table, columns: &[TableColumn<T>], mut rows: Vec<&T>, key: &dyn Fn(&T) -> String, toolbar: Vec<Element<'a, RuWalletMessage>>, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let search = self.search.trim().to_lowercase();
+
+        if !search.is_empty() {
+            rows.retain(|row| columns.iter().any(|column| (column.format)(*row).to_lowercase().contains(&search)));
+        }
+
+        if let Some(compare) = self.sort_column.and_then(|column| columns.get(column)).and_then(|column| column.compare.as_ref()) {
+            let ascending = self.ascending;
+
+            rows.sort_by(|a, b| {
+                let ordering = compare(*a, *b);
+                if ascending { ordering } else { ordering.reverse() }
+            });
+        }
+
+        let total = rows.len();
+        let range = self.page_range(total);
+        let pages = page_count(total);
+        let rows = &rows[range.clone()];
+
+        let mut content = Column::new()
+            .width(Length::Fill)
+            .spacing(10);
+
+        if !self.search_placeholder.is_empty() || !toolbar.is_empty() {
+            let mut toolbar_row = Row::new()
+                .spacing(20)
+                .align_items(Align::Center);
+
+            if !self.search_placeholder.is_empty() {
+                toolbar_row = toolbar_row.push(
+                    TextInput::new(
+                        &mut self.search_input_state,
+                        &self.search_placeholder,
+                        &mut self.search,
+                        move |value| RuWalletMessage::SearchTable(table, value)
+                    )
+                    .padding(10)
+                    .size(18)
+                    .font(ROBOTO)
+                    .style(InputStyle(palette))
+                );
+            }
+
+            for element in toolbar {
+                toolbar_row = toolbar_row.push(element);
+            }
+
+            content = content.push(toolbar_row);
+        }
+
+        self.header_button_states.resize_with(columns.len(), Default::default);
+
+        // same padding as the rows so the headers line up with the cells
+        let mut header = Row::new()
+            .align_items(Align::Start)
+            .spacing(10)
+            .padding(4);
+
+        for (n, (column, button_state)) in columns.iter().zip(self.header_button_states.iter_mut()).enumerate() {
+
+            let title = match (self.sort_column == Some(n), self.ascending) {
+                (true, true) => format!("{} ^", column.title),
+                (true, false) => format!("{} v", column.title),
+                (false, _) => column.title.clone(),
+            };
+
+            let mut header_button = Button::new(
+                button_state,
+                Text::new(title)
+                    .font(ROBOTO_BOLD)
+                    .width(Length::Fill)
+                    .size(18)
+                    .horizontal_alignment(column.alignment)
+            )
+            .width(Length::Units(column.width))
+            .padding(0)
+            .style(HeaderStyle(palette));
+
+            if column.compare.is_some() {
+                header_button = header_button.on_press(RuWalletMessage::SortTable(table, n));
+            }
+
+            header = header.push(header_button);
+        }
+
+        content = content.push(header);
+
+        self.row_button_states.resize_with(rows.len(), Default::default);
+        self.copy_button_states.resize_with(rows.len(), Default::default);
+
+        let states = self.row_button_states.iter_mut().zip(self.copy_button_states.iter_mut());
+
+        for (row, (row_button_state, copy_button_state)) in rows.iter().copied().zip(states) {
+
+            let row_key = key(row);
+
+            let mut cells = Row::new()
+                .align_items(Align::Center)
+                .spacing(10);
+
+            for column in columns {
+                let mut text = Text::new((column.format)(row))
+                    .font(ROBOTO)
+                    .width(Length::Units(column.width))
+                    .size(20)
+                    .horizontal_alignment(column.alignment);
+
+                if let Some(color) = &column.color {
+                    text = text.color(color(row));
+                }
+
+                cells = cells.push(text);
+            }
+
+            cells = cells.push(
+                Button::new(copy_button_state, Text::new("Copy").size(14))
+                    .padding(4)
+                    .style(ButtonStyle(palette))
+                    .on_press(RuWalletMessage::Copy(row_key.clone()))
+            );
+
+            let selected = self.selected.as_deref() == Some(row_key.as_str());
+
+            content = content.push(
+                Button::new(row_button_state, cells)
+                    .width(Length::Fill)
+                    .padding(4)
+                    .style(RowStyle { palette, selected })
+                    .on_press(RuWalletMessage::SelectRow(table, row_key))
+            );
+        }
+
+        let page_text = if total == 0 {
+            "No matching rows".to_string()
+        } else {
+            format!("{}-{} of {} (page {} of {})", range.start + 1, range.end, total, self.page + 1, pages)
+        };
+
+        let mut previous_page_button = Button::new(&mut self.previous_page_button_state, Text::new("Previous"))
+            .padding(10)
+            .style(ButtonStyle(palette));
+
+        if self.page > 0 {
+            previous_page_button = previous_page_button.on_press(RuWalletMessage::ChangePage(table, self.page - 1));
+        }
+
+        let mut next_page_button = Button::new(&mut self.next_page_button_state, Text::new("Next"))
+            .padding(10)
+            .style(ButtonStyle(palette));
+
+        if self.page + 1 < pages {
+            next_page_button = next_page_button.on_press(RuWalletMessage::ChangePage(table, self.page + 1));
+        }
+
+        let page_input = TextInput::new(
+            &mut self.page_input_state,
+            "Page",
+            &mut self.page_input_value,
+            move |value| RuWalletMessage::PageInputChanged(table, value)
+        )
+        .on_submit(RuWalletMessage::JumpToPage(table))
+        .width(Length::Units(80))
+        .padding(10)
+        .size(18)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let jump_button = Button::new(&mut self.jump_button_state, Text::new("Go"))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::JumpToPage(table));
+
+        let pager = Row::new()
+            .spacing(10)
+            .align_items(Align::Center)
+            .push(
+                Text::new(page_text)
+                    .font(ROBOTO)
+                    .width(Length::Fill)
+                    .size(18)
+            )
+            .push(previous_page_button)
+            .push(next_page_button)
+            .push(page_input)
+            .push(jump_button);
+
+        content.push(pager).into()
+    }
+}
+
+// at least one page, so an empty list still shows "page 1 of 1"
+fn page_count(total: usize) -> usize {
+    ((total + PAGE_SIZE - 1) / PAGE_SIZE).max(1)
+}
@@ -0,0 +1,280 @@
+use iced::{button, pick_list, Align, Button, Column, Element, HorizontalAlignment, Length, PickList, Row, Text};
+
+use bdk::KeychainKind;
+use rwallet::format::{format_fiat_signed, BitcoinUnit};
+use rwallet::prices::PriceHistory;
+use rwallet::wallet_service::address_label;
+use rwallet::{AddressRow, TransactionRow, UTXORow};
+use crate::table::{DataTable, TableColumn};
+use crate::theme::{ButtonStyle, Palette, PickListStyle};
+use crate::{RuWalletMessage, Table, ROBOTO, ROBOTO_BOLD};
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressFilter {
+    All,
+    Used,
+    Unused,
+    WithBalance,
+}
+
+impl Default for AddressFilter {
+    fn default() -> Self {
+        AddressFilter::All
+    }
+}
+
+impl fmt::Display for AddressFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressFilter::All => write!(f, "All"),
+            AddressFilter::Used => write!(f, "Used"),
+            AddressFilter::Unused => write!(f, "Unused"),
+            AddressFilter::WithBalance => write!(f, "With balance"),
+        }
+    }
+}
+
+pub static ADDRESS_FILTERS: [AddressFilter; 4] = [AddressFilter::All, AddressFilter::Used, AddressFilter::Unused, AddressFilter::WithBalance];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionFilter {
+    All,
+    Incoming,
+    Outgoing,
+    Pending,
+}
+
+impl Default for TransactionFilter {
+    fn default() -> Self {
+        TransactionFilter::All
+    }
+}
+
+impl fmt::Display for TransactionFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionFilter::All => write!(f, "All"),
+            TransactionFilter::Incoming => write!(f, "Incoming"),
+            TransactionFilter::Outgoing => write!(f, "Outgoing"),
+            TransactionFilter::Pending => write!(f, "Pending"),
+        }
+    }
+}
+
+pub static TRANSACTION_FILTERS: [TransactionFilter; 4] = [TransactionFilter::All, TransactionFilter::Incoming, TransactionFilter::Outgoing, TransactionFilter::Pending];
+
+#[derive(Debug)]
+pub struct AddressTable {
+    pub table: DataTable,
+    pub filter: AddressFilter,
+    filter_pick_list_state: pick_list::State<AddressFilter>,
+    csv_button_state: button::State
+}
+
+impl Default for AddressTable {
+    fn default() -> Self {
+        AddressTable {
+            table: DataTable::searchable("Search address or label"),
+            filter: AddressFilter::default(),
+            filter_pick_list_state: pick_list::State::default(),
+            csv_button_state: button::State::default()
+        }
+    }
+}
+
+impl AddressTable {
+
+    pub fn view<'a>(&'a mut self, receiving: &'a [AddressRow], change: &'a [AddressRow], unit: BitcoinUnit, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let filter = self.filter;
+
+        let rows: Vec<(KeychainKind, &AddressRow)> = receiving.iter().map(|row| (KeychainKind::External, row))
+            .chain(change.iter().map(|row| (KeychainKind::Internal, row)))
+            .filter(|(_, row)| match filter {
+                AddressFilter::All => true,
+                AddressFilter::Used => row.tx_count > 0,
+                AddressFilter::Unused => row.tx_count == 0,
+                AddressFilter::WithBalance => row.balance > 0,
+            })
+            .collect();
+
+        let columns = [
+            TableColumn::new("Index", 50, HorizontalAlignment::Left, |(_, row): &(KeychainKind, &AddressRow)| row.index.to_string())
+                .sort(|(_, a), (_, b)| a.index.cmp(&b.index)),
+            TableColumn::new("Label", 130, HorizontalAlignment::Left, |(keychain, row): &(KeychainKind, &AddressRow)| address_label(*keychain, row.index as u32))
+                .sort(|(a_keychain, a), (b_keychain, b)| (a_keychain, a.index).cmp(&(b_keychain, b.index))),
+            TableColumn::new("Address", 410, HorizontalAlignment::Left, |(_, row): &(KeychainKind, &AddressRow)| row.address.clone())
+                .sort(|(_, a), (_, b)| a.address.cmp(&b.address)),
+            TableColumn::new(&format!("Balance ({})", unit), 170, HorizontalAlignment::Right, move |(_, row): &(KeychainKind, &AddressRow)| unit.format(row.balance))
+                .sort(|(_, a), (_, b)| a.balance.cmp(&b.balance)),
+            TableColumn::new("Tx Count", 90, HorizontalAlignment::Right, |(_, row): &(KeychainKind, &AddressRow)| row.tx_count.to_string())
+                .sort(|(_, a), (_, b)| a.tx_count.cmp(&b.tx_count)),
+        ];
+
+        let filter_pick_list = PickList::new(
+            &mut self.filter_pick_list_state,
+            &ADDRESS_FILTERS[..],
+            Some(filter),
+            RuWalletMessage::AddressFilterSelected
+        )
+        .padding(10)
+        .text_size(18)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        Column::new()
+            .width(Length::Fill)
+            .spacing(10)
+            .push(table_title("Address List", &mut self.csv_button_state, Table::Addresses, palette))
+            .push(self.table.view(Table::Addresses, &columns, rows.iter().collect(), &|(_, row): &(KeychainKind, &AddressRow)| row.address.clone(), vec![filter_pick_list.into()], palette))
+            .into()
+    }
+}
+
+#[derive(Debug)]
+pub struct UtxoTable {
+    pub table: DataTable,
+    csv_button_state: button::State
+}
+
+impl Default for UtxoTable {
+    fn default() -> Self {
+        UtxoTable {
+            table: DataTable::searchable("Search txid or address"),
+            csv_button_state: button::State::default()
+        }
+    }
+}
+
+impl UtxoTable {
+
+    pub fn view<'a>(&'a mut self, utxo_items: &'a [UTXORow], unit: BitcoinUnit, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let columns = [
+            TableColumn::new("Output Point", 610, HorizontalAlignment::Left, outpoint)
+                .sort(|a: &UTXORow, b: &UTXORow| (&a.txid, a.vout).cmp(&(&b.txid, b.vout))),
+            TableColumn::new("Address", 410, HorizontalAlignment::Left, |row: &UTXORow| row.address.clone())
+                .sort(|a, b| a.address.cmp(&b.address)),
+            TableColumn::new(&format!("Amount ({})", unit), 170, HorizontalAlignment::Right, move |row: &UTXORow| unit.format(row.amount))
+                .sort(|a, b| a.amount.cmp(&b.amount)),
+            TableColumn::new("Height", 110, HorizontalAlignment::Right, |row: &UTXORow| row.height.to_string())
+                .sort(|a, b| a.height.cmp(&b.height)),
+        ];
+
+        Column::new()
+            .width(Length::Fill)
+            .spacing(10)
+            .push(table_title("Unspent List", &mut self.csv_button_state, Table::Utxos, palette))
+            .push(self.table.view(Table::Utxos, &columns, utxo_items.iter().collect(), &outpoint, Vec::new(), palette))
+            .into()
+    }
+}
+
+#[derive(Debug)]
+pub struct TransactionTable {
+    pub table: DataTable,
+    pub filter: TransactionFilter,
+    filter_pick_list_state: pick_list::State<TransactionFilter>,
+    csv_button_state: button::State
+}
+
+impl Default for TransactionTable {
+    fn default() -> Self {
+        TransactionTable {
+            table: DataTable::searchable("Search txid or label"),
+            filter: TransactionFilter::default(),
+            filter_pick_list_state: pick_list::State::default(),
+            csv_button_state: button::State::default()
+        }
+    }
+}
+
+impl TransactionTable {
+
+    pub fn view<'a>(&'a mut self, transaction_items: &'a [TransactionRow], unit: BitcoinUnit, prices: Option<&PriceHistory>, currency: &str, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let filter = self.filter;
+
+        let rows: Vec<&TransactionRow> = transaction_items.iter()
+            .filter(|row| match filter {
+                TransactionFilter::All => true,
+                TransactionFilter::Incoming => row.amount > 0,
+                TransactionFilter::Outgoing => row.amount < 0,
+                TransactionFilter::Pending => row.height == 0,
+            })
+            .collect();
+
+        // unconfirmed transactions are the newest ones
+        let mut columns = vec![
+            TableColumn::new("Transaction Id", 610, HorizontalAlignment::Left, |row: &TransactionRow| row.txid.clone())
+                .sort(|a, b| a.txid.cmp(&b.txid)),
+            TableColumn::new("Addresses", 220, HorizontalAlignment::Left, |row: &TransactionRow| row.labels.join(", ")),
+            TableColumn::new(&format!("Amount ({})", unit), 170, HorizontalAlignment::Right, move |row: &TransactionRow| unit.format_signed(row.amount))
+                .color(move |row: &TransactionRow| palette.amount(row.amount))
+                .sort(|a, b| a.amount.cmp(&b.amount)),
+            TableColumn::new("Height", 110, HorizontalAlignment::Right, |row: &TransactionRow| row.height.to_string())
+                .sort(|a, b| height_key(a.height).cmp(&height_key(b.height))),
+        ];
+
+        // value at confirmation time, next to the amount
+        if let Some(prices) = prices {
+            let currency = currency.to_string();
+            let value = move |row: &TransactionRow| prices.value(row.amount, row.timestamp);
+
+            columns.insert(3, TableColumn::new(&format!("Value ({})", currency), 190, HorizontalAlignment::Right, move |row: &TransactionRow| {
+                value(row).map(|value| format_fiat_signed(value, &currency)).unwrap_or_else(|| "n/a".to_string())
+            })
+            .color(move |row: &TransactionRow| palette.amount(row.amount))
+            .sort(move |a, b| value(a).partial_cmp(&value(b)).unwrap_or(std::cmp::Ordering::Equal)));
+        }
+
+        let filter_pick_list = PickList::new(
+            &mut self.filter_pick_list_state,
+            &TRANSACTION_FILTERS[..],
+            Some(filter),
+            RuWalletMessage::TransactionFilterSelected
+        )
+        .padding(10)
+        .text_size(18)
+        .font(ROBOTO)
+        .style(PickListStyle(palette));
+
+        Column::new()
+            .width(Length::Fill)
+            .spacing(10)
+            .push(table_title("Transaction List", &mut self.csv_button_state, Table::Transactions, palette))
+            .push(self.table.view(Table::Transactions, &columns, rows, &|row: &TransactionRow| row.txid.clone(), vec![filter_pick_list.into()], palette))
+            .into()
+    }
+}
+
+pub fn height_key(height: u32) -> u32 {
+    if height == 0 { u32::MAX } else { height }
+}
+
+fn outpoint(row: &UTXORow) -> String {
+    format!("{}:{}", row.txid, row.vout)
+}
+
+// table title with a button copying the whole table as CSV
+fn table_title<'a>(title: &str, state: &'a mut button::State, table: Table, palette: Palette) -> Row<'a, RuWalletMessage> {
+    let title = Text::new(title)
+        .font(ROBOTO_BOLD)
+        .width(Length::Fill)
+        .size(35)
+        .color(palette.muted)
+        .horizontal_alignment(HorizontalAlignment::Left);
+
+    Row::new()
+        .spacing(20)
+        .align_items(Align::Center)
+        .push(title)
+        .push(
+            Button::new(state, Text::new("Copy as CSV"))
+                .padding(10)
+                .style(ButtonStyle(palette))
+                .on_press(RuWalletMessage::CopyTable(table))
+        )
+}
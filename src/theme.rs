@@ -0,0 +1,268 @@
+use iced::{button, checkbox, container, pick_list, text_input, Background, Color, Vector};
+
+use rwallet::config::Theme;
+
+// Colours every view draws with, one set per theme.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    // sidebar, table headers and tooltips
+    pub surface: Color,
+    pub text: Color,
+    // titles, hints and axes
+    pub muted: Color,
+    pub primary: Color,
+    pub on_primary: Color,
+    pub border: Color,
+    pub hovered: Color,
+    pub selected: Color,
+    // our own inputs and outputs, the balance chart
+    pub accent: Color,
+    pub positive: Color,
+    pub negative: Color,
+    pub warning: Color
+}
+
+pub const LIGHT: Palette = Palette {
+    background: Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+    surface: Color { r: 0.95, g: 0.95, b: 0.95, a: 1.0 },
+    text: Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },
+    muted: Color { r: 0.5, g: 0.5, b: 0.5, a: 1.0 },
+    primary: Color { r: 0.2, g: 0.45, b: 0.75, a: 1.0 },
+    on_primary: Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+    border: Color { r: 0.7, g: 0.7, b: 0.7, a: 1.0 },
+    hovered: Color { r: 0.93, g: 0.93, b: 0.93, a: 1.0 },
+    selected: Color { r: 0.8, g: 0.9, b: 1.0, a: 1.0 },
+    accent: Color { r: 0.1, g: 0.5, b: 0.8, a: 1.0 },
+    positive: Color { r: 0.1, g: 0.6, b: 0.3, a: 1.0 },
+    negative: Color { r: 0.8, g: 0.2, b: 0.2, a: 1.0 },
+    warning: Color { r: 0.9, g: 0.6, b: 0.1, a: 1.0 }
+};
+
+pub const DARK: Palette = Palette {
+    background: Color { r: 0.12, g: 0.12, b: 0.14, a: 1.0 },
+    surface: Color { r: 0.18, g: 0.18, b: 0.21, a: 1.0 },
+    text: Color { r: 0.9, g: 0.9, b: 0.9, a: 1.0 },
+    muted: Color { r: 0.6, g: 0.6, b: 0.65, a: 1.0 },
+    primary: Color { r: 0.3, g: 0.55, b: 0.85, a: 1.0 },
+    on_primary: Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+    border: Color { r: 0.35, g: 0.35, b: 0.4, a: 1.0 },
+    hovered: Color { r: 0.22, g: 0.22, b: 0.26, a: 1.0 },
+    selected: Color { r: 0.2, g: 0.3, b: 0.45, a: 1.0 },
+    accent: Color { r: 0.35, g: 0.7, b: 1.0, a: 1.0 },
+    positive: Color { r: 0.3, g: 0.8, b: 0.45, a: 1.0 },
+    negative: Color { r: 0.95, g: 0.4, b: 0.4, a: 1.0 },
+    warning: Color { r: 1.0, g: 0.7, b: 0.25, a: 1.0 }
+};
+
+pub fn palette(theme: Theme) -> Palette {
+    match theme {
+        Theme::Light => LIGHT,
+        Theme::Dark => DARK,
+    }
+}
+
+impl Palette {
+    // green for amounts entering the wallet, red for amounts leaving it
+    pub fn amount(&self, amount: i128) -> Color {
+        match amount.signum() {
+            1 => self.positive,
+            -1 => self.negative,
+            _ => self.text,
+        }
+    }
+}
+
+// The page and the sidebar; children inherit the text colour.
+pub struct ContainerStyle {
+    pub palette: Palette,
+    pub surface: bool
+}
+
+impl container::StyleSheet for ContainerStyle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.palette.text),
+            background: Some(Background::Color(if self.surface { self.palette.surface } else { self.palette.background })),
+            ..container::Style::default()
+        }
+    }
+}
+
+pub struct ButtonStyle(pub Palette);
+
+impl button::StyleSheet for ButtonStyle {
+    fn active(&self) -> button::Style {
+        button::Style {
+            shadow_offset: Vector::new(0.0, 0.0),
+            background: Some(Background::Color(self.0.primary)),
+            border_radius: 4.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+            text_color: self.0.on_primary
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            shadow_offset: Vector::new(0.0, 1.0),
+            ..self.active()
+        }
+    }
+
+    // a disabled button is the current tab or range, or has nothing to act on
+    fn disabled(&self) -> button::Style {
+        button::Style {
+            background: Some(Background::Color(self.0.surface)),
+            border_width: 1.0,
+            border_color: self.0.border,
+            text_color: self.0.muted,
+            ..self.active()
+        }
+    }
+}
+
+// Sortable table headers, flat so they read as labels.
+pub struct HeaderStyle(pub Palette);
+
+impl button::StyleSheet for HeaderStyle {
+    fn active(&self) -> button::Style {
+        button::Style {
+            shadow_offset: Vector::new(0.0, 0.0),
+            background: None,
+            border_radius: 0.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+            text_color: self.0.muted
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            text_color: self.0.text,
+            ..self.active()
+        }
+    }
+
+    fn disabled(&self) -> button::Style {
+        self.active()
+    }
+}
+
+// Flat table rows and wallet list entries, highlighted when hovered or selected.
+pub struct RowStyle {
+    pub palette: Palette,
+    pub selected: bool
+}
+
+impl button::StyleSheet for RowStyle {
+    fn active(&self) -> button::Style {
+        button::Style {
+            shadow_offset: Vector::new(0.0, 0.0),
+            background: if self.selected { Some(Background::Color(self.palette.selected)) } else { None },
+            border_radius: 2.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+            text_color: self.palette.text
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        let active = self.active();
+
+        if self.selected {
+            return active;
+        }
+
+        button::Style {
+            background: Some(Background::Color(self.palette.hovered)),
+            ..active
+        }
+    }
+}
+
+pub struct InputStyle(pub Palette);
+
+impl text_input::StyleSheet for InputStyle {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Background::Color(self.0.background),
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color: self.0.border
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_color: self.0.primary,
+            ..self.active()
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        self.0.muted
+    }
+
+    fn value_color(&self) -> Color {
+        self.0.text
+    }
+
+    fn selection_color(&self) -> Color {
+        self.0.selected
+    }
+}
+
+pub struct PickListStyle(pub Palette);
+
+impl pick_list::StyleSheet for PickListStyle {
+    fn menu(&self) -> pick_list::Menu {
+        pick_list::Menu {
+            text_color: self.0.text,
+            background: Background::Color(self.0.surface),
+            border_width: 1.0,
+            border_color: self.0.border,
+            selected_text_color: self.0.on_primary,
+            selected_background: Background::Color(self.0.primary)
+        }
+    }
+
+    fn active(&self) -> pick_list::Style {
+        pick_list::Style {
+            text_color: self.0.text,
+            background: Background::Color(self.0.surface),
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color: self.0.border,
+            icon_size: 0.7
+        }
+    }
+
+    fn hovered(&self) -> pick_list::Style {
+        pick_list::Style {
+            border_color: self.0.primary,
+            ..self.active()
+        }
+    }
+}
+
+pub struct CheckboxStyle(pub Palette);
+
+impl checkbox::StyleSheet for CheckboxStyle {
+    fn active(&self, _is_checked: bool) -> checkbox::Style {
+        checkbox::Style {
+            background: Background::Color(self.0.surface),
+            checkmark_color: self.0.primary,
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color: self.0.border
+        }
+    }
+
+    fn hovered(&self, is_checked: bool) -> checkbox::Style {
+        checkbox::Style {
+            background: Background::Color(self.0.hovered),
+            ..self.active(is_checked)
+        }
+    }
+}
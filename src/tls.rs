@@ -0,0 +1,351 @@
+use bdk::bitcoin::base64;
+use bdk::bitcoin::hashes::hex::ToHex;
+use bdk::bitcoin::hashes::{sha256, Hash};
+use bdk::electrum_client::Error;
+use native_tls::{TlsConnector, TlsStream};
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::electrum::ServerSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TlsMode {
+    // certificate must chain to a trusted CA and match the host name
+    ValidateDomain,
+    // accept the first certificate seen and require the same one afterwards
+    TrustOnFirstUse,
+    // require the certificate stored in `certificate_path`
+    PinnedCertificate
+}
+
+impl Default for TlsMode {
+    fn default() -> Self {
+        TlsMode::ValidateDomain
+    }
+}
+
+impl fmt::Display for TlsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsMode::ValidateDomain => write!(f, "Validate domain"),
+            TlsMode::TrustOnFirstUse => write!(f, "Trust on first use"),
+            TlsMode::PinnedCertificate => write!(f, "Pinned certificate"),
+        }
+    }
+}
+
+pub static TLS_MODES: [TlsMode; 3] = [TlsMode::ValidateDomain, TlsMode::TrustOnFirstUse, TlsMode::PinnedCertificate];
+
+#[derive(Debug, Clone, Default, PartialEq, Hash, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub mode: TlsMode,
+    // sha256 of the DER certificate, learned on first use
+    pub fingerprint: String,
+    // PEM or DER file
+    pub certificate_path: String
+}
+
+impl TlsConfig {
+    // self-signed certificates are fine once we check the fingerprint ourselves
+    pub fn validate_domain(&self) -> bool {
+        self.mode == TlsMode::ValidateDomain
+    }
+}
+
+pub enum CertificateCheck {
+    // nothing to check, or the certificate matched
+    Trusted,
+    // trust on first use without a stored fingerprint, the caller should persist it
+    FirstUse(String)
+}
+
+// checks the certificate presented by the server against the configured TLS options
+pub fn check_certificate(server: &ServerSettings, timeout: Duration) -> Result<CertificateCheck, Error> {
+    if !server.url.starts_with("ssl://") || server.tls.mode == TlsMode::ValidateDomain {
+        return Ok(CertificateCheck::Trusted);
+    }
+
+    let (_, check) = connect_verified(server, timeout)?;
+
+    Ok(check)
+}
+
+// Loopback endpoint for an Electrum client that cannot check a fingerprint
+// itself. Its one connection is forwarded over a TLS session whose certificate
+// was checked, so the client only ever talks to the verified server. The relay
+// ends with that connection, a client reconnecting gets a new relay from the pool.
+pub struct TlsRelay {
+    addr: SocketAddr,
+    listener: TcpListener,
+    session: TlsStream<Transport>
+}
+
+impl TlsRelay {
+
+    // the session is opened here, so a certificate mismatch is reported to the caller
+    pub fn start(server: &ServerSettings, timeout: Duration) -> Result<Self, Error> {
+        let (session, _) = connect_verified(server, timeout)?;
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        Ok(TlsRelay { addr, listener, session })
+    }
+
+    pub fn url(&self) -> String {
+        format!("tcp://{}", self.addr)
+    }
+
+    // takes the connection the client just made to `url` and closes the listener.
+    // Any other connection waiting there is a local process trying to use the
+    // tunnel, the relay is then abandoned instead of guessing which one is ours.
+    pub fn attach(self) -> Result<(), Error> {
+        let TlsRelay { listener, session, .. } = self;
+
+        listener.set_nonblocking(true)?;
+
+        let mut connections = Vec::new();
+        loop {
+            match listener.accept() {
+                Ok((local, _)) => connections.push(local),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        if connections.len() != 1 {
+            return Err(Error::Message(format!("Expected one connection to the TLS relay, found {}", connections.len())));
+        }
+
+        let local = connections.remove(0);
+        local.set_nonblocking(false)?;
+
+        thread::spawn(move || relay(local, session));
+
+        Ok(())
+    }
+}
+
+// TCP connection under a relayed TLS session. During the handshake the session
+// reads the socket itself, afterwards the relay reads it on a thread of its own
+// and hands the bytes in, so the session never waits on the server while the
+// client has a request to send.
+#[derive(Debug)]
+struct Transport {
+    tcp: TcpStream,
+    // `None` until the relay takes over the reads
+    incoming: Option<VecDeque<u8>>
+}
+
+impl Read for Transport {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match &mut self.incoming {
+            None => self.tcp.read(buffer),
+            Some(incoming) if incoming.is_empty() => Err(ErrorKind::WouldBlock.into()),
+            Some(incoming) => incoming.read(buffer),
+        }
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.tcp.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.tcp.flush()
+    }
+}
+
+enum RelayEvent {
+    // plain bytes from the client
+    Local(Vec<u8>),
+    // encrypted bytes from the server
+    Remote(Vec<u8>),
+    Closed
+}
+
+pub fn fingerprint(der: &[u8]) -> String {
+    sha256::Hash::hash(der).into_inner().to_hex()
+}
+
+// opens a TLS session and keeps it only if its certificate matches the configured one
+fn connect_verified(server: &ServerSettings, timeout: Duration) -> Result<(TlsStream<Transport>, CertificateCheck), Error> {
+    let stream = open_stream(server, timeout)?;
+
+    let certificate = stream.peer_certificate()
+        .map_err(|e| Error::Message(e.to_string()))?
+        .ok_or_else(|| Error::Message(format!("{} presented no certificate", server.url)))?;
+
+    let fingerprint = fingerprint(&certificate.to_der().map_err(|e| Error::Message(e.to_string()))?);
+
+    let expected = match server.tls.mode {
+        TlsMode::TrustOnFirstUse if server.tls.fingerprint.is_empty() => {
+            return Ok((stream, CertificateCheck::FirstUse(fingerprint)));
+        },
+        TlsMode::TrustOnFirstUse => server.tls.fingerprint.to_lowercase(),
+        _ => {
+            let der = read_certificate_file(&server.tls.certificate_path)?;
+            self::fingerprint(&der)
+        },
+    };
+
+    if expected != fingerprint {
+        return Err(Error::Message(format!(
+            "Certificate mismatch for {}: expected fingerprint {} but the server presented {}. \
+             The server may have rotated its certificate, or someone is intercepting the connection.",
+            server.url, expected, fingerprint)));
+    }
+
+    Ok((stream, CertificateCheck::Trusted))
+}
+
+// accepts any certificate, `connect_verified` checks the fingerprint afterwards
+fn open_stream(server: &ServerSettings, timeout: Duration) -> Result<TlsStream<Transport>, Error> {
+    server.check()?;
+
+    let (host, port) = host_port(&server.url)?;
+
+    let tcp = if server.proxy.is_enabled() {
+        let proxy = server.proxy.address.trim();
+        let stream = if server.proxy.username.is_empty() {
+            socks::Socks5Stream::connect(proxy, (host.as_str(), port))?
+        } else {
+            socks::Socks5Stream::connect_with_password(proxy, (host.as_str(), port), &server.proxy.username, &server.proxy.password)?
+        };
+        stream.into_inner()
+    } else {
+        TcpStream::connect((host.as_str(), port))?
+    };
+
+    tcp.set_read_timeout(Some(timeout))?;
+    tcp.set_write_timeout(Some(timeout))?;
+
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| Error::Message(e.to_string()))?;
+
+    connector.connect(&host, Transport { tcp, incoming: None })
+        .map_err(|e| Error::Message(format!("TLS handshake with {} failed: {}", server.url, e)))
+}
+
+// copies bytes both ways until either side closes. Each socket is read by a
+// blocking thread of its own, the TLS session cannot be shared between threads
+// so this one owns it and does the encryption for both directions.
+fn relay(mut local: TcpStream, mut session: TlsStream<Transport>) {
+    let (sender, events) = mpsc::channel();
+
+    // an idle subscription can stay silent much longer than the connect timeout
+    let readers = session.get_ref().tcp.set_read_timeout(None)
+        .and_then(|_| local.try_clone())
+        .and_then(|from| session.get_ref().tcp.try_clone().map(|remote| (from, remote)));
+
+    let (from_local, from_remote) = match readers {
+        Ok(readers) => readers,
+        Err(_) => return,
+    };
+
+    let local_sender = sender.clone();
+    thread::spawn(move || read_side(from_local, RelayEvent::Local, local_sender));
+    thread::spawn(move || read_side(from_remote, RelayEvent::Remote, sender));
+
+    session.get_mut().incoming = Some(VecDeque::new());
+
+    let mut buffer = [0u8; 8192];
+
+    // the server may have sent data along with the end of the handshake
+    if forward_remote(&mut session, &mut local, &mut buffer) {
+        for event in events {
+            let open = match event {
+                RelayEvent::Local(bytes) => session.write_all(&bytes).and_then(|_| session.flush()).is_ok(),
+                RelayEvent::Remote(bytes) => {
+                    if let Some(incoming) = &mut session.get_mut().incoming {
+                        incoming.extend(bytes);
+                    }
+                    forward_remote(&mut session, &mut local, &mut buffer)
+                },
+                RelayEvent::Closed => false,
+            };
+
+            if !open {
+                break;
+            }
+        }
+    }
+
+    // unblocks the reader still waiting on the other side
+    let _ = session.shutdown();
+    let _ = session.get_ref().tcp.shutdown(Shutdown::Both);
+    let _ = local.shutdown(Shutdown::Both);
+}
+
+// hands everything read from `from` to the relay, until the socket or the relay closes
+fn read_side(mut from: TcpStream, event: fn(Vec<u8>) -> RelayEvent, sender: Sender<RelayEvent>) {
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        match from.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => if sender.send(event(buffer[..n].to_vec())).is_err() {
+                return;
+            },
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        }
+    }
+
+    let _ = sender.send(RelayEvent::Closed);
+}
+
+// passes on whatever the session can decrypt so far, false once either side is closed
+fn forward_remote(session: &mut TlsStream<Transport>, local: &mut TcpStream, buffer: &mut [u8]) -> bool {
+    loop {
+        match session.read(buffer) {
+            Ok(0) => return false,
+            Ok(n) => if local.write_all(&buffer[..n]).is_err() {
+                return false;
+            },
+            Err(e) => return e.kind() == ErrorKind::WouldBlock,
+        }
+    }
+}
+
+fn read_certificate_file(path: &str) -> Result<Vec<u8>, Error> {
+    let content = fs::read(path)
+        .map_err(|e| Error::Message(format!("Cannot read pinned certificate {}: {}", path, e)))?;
+
+    let pem = match String::from_utf8(content) {
+        Ok(text) if text.contains("-----BEGIN CERTIFICATE-----") => text,
+        Ok(text) => return Ok(text.into_bytes()),
+        Err(e) => return Ok(e.into_bytes()),
+    };
+
+    let body: String = pem.lines()
+        .skip_while(|line| !line.starts_with("-----BEGIN CERTIFICATE-----"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END CERTIFICATE-----"))
+        .collect();
+
+    base64::decode(body.trim())
+        .map_err(|e| Error::Message(format!("Invalid pinned certificate {}: {}", path, e)))
+}
+
+fn host_port(url: &str) -> Result<(String, u16), Error> {
+    let address = url.split("://").last().unwrap_or(url);
+    let invalid = || Error::Message(format!("Invalid server url {}", url));
+
+    let (host, port) = address.rsplit_once(':').ok_or_else(invalid)?;
+    let port = port.parse::<u16>().map_err(|_| invalid())?;
+
+    Ok((host.to_string(), port))
+}
@@ -0,0 +1,150 @@
+use iced::{button, Align, Button, Color, Column, Element, HorizontalAlignment, Length, Row, Text};
+
+use rwallet::format::{format_timestamp, BitcoinUnit};
+use rwallet::wallet_service::{TxInputRow, TxOutputRow};
+use rwallet::TransactionDetail;
+use crate::table::{DataTable, TableColumn};
+use crate::theme::{ButtonStyle, Palette};
+use crate::{RuWalletMessage, Table, ROBOTO, ROBOTO_BOLD};
+
+#[derive(Debug, Default)]
+pub struct TransactionDetailView {
+    back_button_state: button::State,
+
+    pub inputs_table: DataTable,
+
+    pub outputs_table: DataTable
+}
+
+impl TransactionDetailView {
+
+    pub fn view<'a>(&'a mut self, detail: &'a TransactionDetail, unit: BitcoinUnit, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let back_button = Button::new(&mut self.back_button_state, Text::new("Back"))
+            .padding(10)
+            .style(ButtonStyle(palette))
+            .on_press(RuWalletMessage::CloseDetail);
+
+        let detail_title = Text::new("Transaction")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let fee = match (detail.fee, detail.fee_rate) {
+            (Some(fee), Some(fee_rate)) => format!("{} ({:.2} sat/vB)", unit.format_with_unit(fee), fee_rate),
+            _ => "unknown".to_string(),
+        };
+
+        let confirmation = match detail.block_time {
+            Some(block_time) => format!("{} (block {}, {} UTC)", detail.confirmations, detail.height, format_timestamp(block_time)),
+            None => "unconfirmed".to_string(),
+        };
+
+        let mut page = Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .align_items(Align::Center)
+                    .push(detail_title)
+                    .push(back_button)
+            )
+            .push(field("Transaction Id", detail.txid.clone()))
+            .push(colored_field("Net Amount", format!("{} {}", unit.format_signed(detail.amount), unit), Some(palette.amount(detail.amount))))
+            .push(field("Fee", fee))
+            .push(field("Confirmations", confirmation))
+            .push(field("Size", format!("{} bytes, {} vbytes, {} weight units", detail.size, detail.vsize, detail.weight)))
+            .push(field("Version", detail.version.to_string()))
+            .push(field("Locktime", detail.lock_time.to_string()))
+            .push(field("RBF", if detail.rbf { "signalled" } else { "not signalled" }.to_string()));
+
+        page = page.push(section_title(format!("Inputs ({})", detail.inputs.len()), palette));
+
+        let input_columns = [
+            TableColumn::new("Output Point", 610, HorizontalAlignment::Left, |input: &TxInputRow| input.outpoint.clone()),
+            TableColumn::new("Address", 410, HorizontalAlignment::Left, |input: &TxInputRow| {
+                input.address.clone().unwrap_or_else(|| "unknown address".to_string())
+            })
+            .color(move |input: &TxInputRow| mine_color(input.is_mine, palette)),
+            TableColumn::new(&format!("Amount ({})", unit), 170, HorizontalAlignment::Right, move |input: &TxInputRow| {
+                input.amount.map(|amount| unit.format(amount)).unwrap_or_else(|| "?".to_string())
+            }),
+            TableColumn::new("", 60, HorizontalAlignment::Left, |input: &TxInputRow| mine_label(input.is_mine))
+                .color(move |input: &TxInputRow| mine_color(input.is_mine, palette)),
+        ];
+
+        page = page.push(self.inputs_table.view(Table::TransactionInputs, &input_columns, detail.inputs.iter().collect(), &|input: &TxInputRow| input.outpoint.clone(), Vec::new(), palette));
+
+        page = page.push(section_title(format!("Outputs ({})", detail.outputs.len()), palette));
+
+        let output_columns = [
+            TableColumn::new("Index", 50, HorizontalAlignment::Left, |output: &TxOutputRow| output.vout.to_string()),
+            TableColumn::new("Address", 410, HorizontalAlignment::Left, |output: &TxOutputRow| output.address.clone())
+                .color(move |output: &TxOutputRow| mine_color(output.is_mine, palette)),
+            TableColumn::new(&format!("Amount ({})", unit), 170, HorizontalAlignment::Right, move |output: &TxOutputRow| unit.format(output.amount)),
+            TableColumn::new("", 60, HorizontalAlignment::Left, |output: &TxOutputRow| mine_label(output.is_mine))
+                .color(move |output: &TxOutputRow| mine_color(output.is_mine, palette)),
+        ];
+
+        let txid = &detail.txid;
+
+        page = page.push(self.outputs_table.view(Table::TransactionOutputs, &output_columns, detail.outputs.iter().collect(), &|output: &TxOutputRow| format!("{}:{}", txid, output.vout), Vec::new(), palette));
+
+        page = page
+            .push(section_title("Raw Transaction".to_string(), palette))
+            .push(
+                Text::new(&detail.raw_hex)
+                    .font(ROBOTO)
+                    .width(Length::Fill)
+                    .size(14)
+            );
+
+        page.into()
+    }
+}
+
+fn section_title(title: String, palette: Palette) -> Text {
+    Text::new(title)
+        .font(ROBOTO_BOLD)
+        .width(Length::Fill)
+        .size(25)
+        .color(palette.muted)
+        .horizontal_alignment(HorizontalAlignment::Left)
+}
+
+fn field<'a>(label: &str, value: String) -> Row<'a, RuWalletMessage> {
+    colored_field(label, value, None)
+}
+
+// without a colour the value takes the text colour of the theme
+fn colored_field<'a>(label: &str, value: String, color: Option<Color>) -> Row<'a, RuWalletMessage> {
+    let mut value_text = Text::new(value)
+        .font(ROBOTO)
+        .width(Length::Fill)
+        .size(20);
+
+    if let Some(color) = color {
+        value_text = value_text.color(color);
+    }
+
+    Row::new()
+        .spacing(10)
+        .push(
+            Text::new(label)
+                .font(ROBOTO_BOLD)
+                .width(Length::Units(200))
+                .size(20)
+        )
+        .push(value_text)
+}
+
+// inputs and outputs that belong to the wallet stand out
+fn mine_color(is_mine: bool, palette: Palette) -> Color {
+    if is_mine { palette.accent } else { palette.text }
+}
+
+fn mine_label(is_mine: bool) -> String {
+    if is_mine { "mine" } else { "" }.to_string()
+}
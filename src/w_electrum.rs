@@ -1,40 +1,17 @@
-use bdk::bitcoin::secp256k1::Secp256k1;
-use bdk::bitcoin::util::bip32::{DerivationPath, KeySource, ExtendedPrivKey};
-use bdk::descriptor::Segwitv0;
-use bdk::keys::{ExtendedKey, DerivableKey, DescriptorKey, DescriptorKey::Secret};
-use bdk::keys::bip39::Mnemonic;
-use bdk::template::Bip84;
-use bdk::wallet::export::WalletExport;
-use bdk::{Wallet, SignOptions, KeychainKind};
-use bdk::database::MemoryDatabase;
+use bdk::{Wallet, KeychainKind, Error, BlockTime, LocalUtxo, TransactionDetails};
+use bdk::database::BatchDatabase;
+use bdk::descriptor::derived::AsDerived;
+use bdk::miniscript::descriptor::DescriptorTrait;
 use bdk::blockchain::{noop_progress, ElectrumBlockchain};
-use bdk::bitcoin::{Network, Address, Transaction, Script};
+use bdk::sled::{self, Tree};
+use bdk::bitcoin::{Network, Address, OutPoint, Script, Transaction};
 
-use bdk::electrum_client::{Client, ElectrumApi};
-use bdk::wallet::AddressIndex;
+use bdk::electrum_client::{self, ElectrumApi};
+use bdk::wallet::AddressInfo;
 
-use std::str::FromStr;
+use std::path::Path;
 
-/*use crate::wallet_common::{get_descriptors, build_signed_tx, mnemonic_to_xprv};
-
-pub fn load_or_create_wallet(electrum_url: &str, network: &Network, xpriv: &ExtendedPrivKey)  -> Wallet<ElectrumBlockchain, MemoryDatabase>
-{
-    // Apparently it works only with Electrs (not EletrumX)
-    let client = Client::new(electrum_url).unwrap();
-
-    let wallet = Wallet::new(
-        Bip84(xpriv.clone(), KeychainKind::External),
-        Some(Bip84(*xpriv, KeychainKind::Internal)),
-        *network,
-        MemoryDatabase::default(),
-        ElectrumBlockchain::from(client)
-    ).unwrap();
-
-    wallet.sync(noop_progress(), None).unwrap();
-
-    wallet
-}
-*/
+use crate::electrum::ElectrumConnector;
 
 pub struct AdditionalAddrInfo {
     pub index: u64,
@@ -43,91 +20,162 @@ pub struct AdditionalAddrInfo {
     pub balance: u64
 }
 
-pub fn get_batch_history_and_balance(electrum_url: &str, scripts: &Vec::<Script>) -> Vec::<AdditionalAddrInfo> {
-    // let client = Client::new("ssl://electrum.blockstream.info:60002").unwrap();
-
-    let client = Client::new(electrum_url).unwrap();
+pub fn get_batch_history_and_balance<E: ElectrumApi>(client: &E, network: &Network, scripts: &[Script]) -> Result<Vec<AdditionalAddrInfo>, electrum_client::Error> {
 
-    let history_list = client.batch_script_get_history(scripts).unwrap();
-    let balance_list = client.batch_script_get_balance(scripts).unwrap();
+    let history_list = client.batch_script_get_history(scripts)?;
+    let balance_list = client.batch_script_get_balance(scripts)?;
 
     let mut result = Vec::<AdditionalAddrInfo>::new();
 
-    for n in 0..scripts.len() {
-        let index: u64 = n.try_into().expect("cannot convert");
-        let script = &scripts[n];
-        let address = Address::from_script(&script, Network::Testnet).unwrap().to_string();
-        let balance = balance_list[n].confirmed;
-        let tx_count = history_list[n].len().try_into().expect("cannot convert");;
+    for (index, ((script, history), balance)) in scripts.iter().zip(history_list).zip(balance_list).enumerate() {
+        let address = Address::from_script(script, *network)
+            .ok_or_else(|| electrum_client::Error::Message(format!("script {} has no address form", script)))?;
 
         result.push(AdditionalAddrInfo {
-            index,
-            address,
-            tx_count,
-            balance
+            index: index as u64,
+            address: address.to_string(),
+            tx_count: history.len() as u64,
+            balance: balance.confirmed
         });
-
     }
 
-    return result;
+    Ok(result)
 }
 
-pub fn load_or_create_wallet(electrum_url: &str, network: &Network, external_descriptor: &str, internal_descriptor: &str)  -> Wallet<ElectrumBlockchain, MemoryDatabase>
-{
-    // Apparently it works only with Electrs (not EletrumX)
-    let client = Client::new(electrum_url).unwrap();
-
-    let wallet = Wallet::new(
-        external_descriptor,
-        Some(internal_descriptor),
-        *network,
-        MemoryDatabase::default(),
-        ElectrumBlockchain::from(client)
-    ).unwrap();
-
-    wallet.sync(noop_progress(), None).unwrap();
+// Brings the transactions of `scripts` up to date in the wallet database, the partial
+// counterpart of `Wallet::sync` for the scripts a notification named. Outputs are only
+// recorded with a transaction seen for the first time, a transaction dropped from the
+// mempool stays until the next full sync.
+pub fn sync_scripts<E: ElectrumApi, D: BatchDatabase>(client: &E, database: &mut D, scripts: &[Script]) -> Result<(), Error> {
+    let history_list = client.batch_script_get_history(scripts)?;
+
+    for (script, history) in scripts.iter().zip(history_list) {
+        // a used address must not be handed out again
+        if let (Some((keychain, index)), false) = (database.get_path_from_script_pubkey(script)?, history.is_empty()) {
+            if database.get_last_index(keychain)?.map_or(true, |last| last < index) {
+                database.set_last_index(keychain, index)?;
+            }
+        }
+
+        for entry in history {
+            // zero or less means in the mempool
+            let height = u32::try_from(entry.height).ok().filter(|height| *height > 0);
+
+            match database.get_tx(&entry.tx_hash, false)? {
+                Some(mut details) => {
+                    if details.confirmation_time.as_ref().map(|time| time.height) != height {
+                        details.confirmation_time = block_time(client, height)?;
+                        database.set_tx(&details)?;
+                    }
+                },
+                None => {
+                    let transaction = client.transaction_get(&entry.tx_hash)?;
+                    let confirmation_time = block_time(client, height)?;
+                    let details = record_transaction(client, database, transaction, confirmation_time)?;
+                    database.set_tx(&details)?;
+                },
+            }
+        }
+    }
 
-    wallet
+    Ok(())
 }
 
-pub fn run(network: Network, external_descriptor: &str, internal_descriptor: &str, electrum_url: &str) {
-
-    //let xpriv = mnemonic_to_xprv(&network, &mnemonic_words);
-
-    let wallet = load_or_create_wallet(electrum_url, &network, external_descriptor, internal_descriptor);
-
-    for n in 0..10 {
-        let address = wallet.get_address(AddressIndex::Peek(n)).unwrap().address;
-        println!("address {}: {}", n, address);
+fn block_time<E: ElectrumApi>(client: &E, height: Option<u32>) -> Result<Option<BlockTime>, Error> {
+    match height {
+        Some(height) => Ok(Some(BlockTime {
+            height,
+            timestamp: client.block_header(height as usize)?.time as u64
+        })),
+        None => Ok(None),
     }
+}
 
-    /* let address = wallet.get_address(AddressIndex::New).unwrap().address;
-
-    println!("address: {}", address);
+// stores the outputs a new transaction pays us, forgets the ones it spends and works out its amounts
+fn record_transaction<E: ElectrumApi, D: BatchDatabase>(client: &E, database: &mut D, transaction: Transaction, confirmation_time: Option<BlockTime>) -> Result<TransactionDetails, Error> {
+    let txid = transaction.txid();
+
+    let mut sent = 0;
+    let mut received = 0;
+    // unknown as soon as one previous output can't be found, e.g. for a coinbase
+    let mut input_total = Some(0u64);
+
+    for input in &transaction.input {
+        let outpoint = input.previous_output;
+
+        let previous = match database.get_raw_tx(&outpoint.txid)? {
+            Some(previous) => Some(previous),
+            None if transaction.is_coin_base() => None,
+            None => Some(client.transaction_get(&outpoint.txid)?),
+        };
+
+        match previous.and_then(|previous| previous.output.get(outpoint.vout as usize).cloned()) {
+            Some(output) => {
+                if database.get_path_from_script_pubkey(&output.script_pubkey)?.is_some() {
+                    sent += output.value;
+                    database.del_utxo(&outpoint)?;
+                }
+                input_total = input_total.map(|total| total + output.value);
+            },
+            None => input_total = None,
+        }
+    }
 
-    let balance = wallet.get_balance().unwrap();
+    for (vout, output) in transaction.output.iter().enumerate() {
+        if let Some((keychain, _)) = database.get_path_from_script_pubkey(&output.script_pubkey)? {
+            received += output.value;
+            database.set_utxo(&LocalUtxo {
+                outpoint: OutPoint::new(txid, vout as u32),
+                txout: output.clone(),
+                keychain
+            })?;
+        }
+    }
 
-    println!("balance: {}", balance);*/
+    let output_total: u64 = transaction.output.iter().map(|output| output.value).sum();
+
+    Ok(TransactionDetails {
+        txid,
+        received,
+        sent,
+        fee: input_total.and_then(|total| total.checked_sub(output_total)),
+        confirmation_time,
+        verified: false,
+        transaction: Some(transaction)
+    })
+}
 
-    /*if balance > 100 {
+pub fn open_database(db_path: &Path) -> Result<Tree, Error> {
+    let db = sled::open(db_path)?;
 
-        let recipient_address = "tb1qfhg76n90tc985rvwyw3cg3x6pnhqd32yddw3pw";
+    Ok(db.open_tree("wallet")?)
+}
 
-        let amount = 9359;
+pub fn load_or_create_wallet(connector: &dyn ElectrumConnector, network: &Network, external_descriptor: &str, internal_descriptor: &str, db_path: &Path)  -> Result<Wallet<ElectrumBlockchain, Tree>, Error>
+{
+    // Apparently it works only with Electrs (not EletrumX)
+    let client = connector.connect()?;
 
-        let tx = build_signed_tx(&wallet, recipient_address, amount);
+    let wallet = Wallet::new(
+        external_descriptor,
+        Some(internal_descriptor),
+        *network,
+        open_database(db_path)?,
+        ElectrumBlockchain::from(client)
+    )?;
 
-        let tx_id = wallet.broadcast(&tx).unwrap();
+    wallet.sync(noop_progress(), None)?;
 
-        println!("tx id: {}", tx_id.to_string());
-    } else {
-        println!("Insufficient Funds. Fund the wallet with the address above");
-    }
+    Ok(wallet)
+}
 
-    let export = WalletExport::export_wallet(&wallet, "exported wallet", true)
-        .map_err(ToString::to_string)
-        .map_err(bdk::Error::Generic).unwrap();
+pub fn peek_change_address(wallet: &Wallet<ElectrumBlockchain, Tree>, index: u32) -> Result<AddressInfo, Error> {
 
-    println!("------\nWallet Backup: {}", export.to_string());*/
+    let result_descriptor = wallet.get_descriptor_for_keychain(KeychainKind::Internal);
 
-}
\ No newline at end of file
+    result_descriptor
+        .as_derived(index, wallet.secp_ctx())
+        .address(wallet.network())
+        .map(|address| AddressInfo { index, address })
+        .map_err(|_| Error::ScriptDoesntHaveAddressForm)
+}
@@ -0,0 +1,137 @@
+use iced::{button, text_input, scrollable, Button, Column, Element, HorizontalAlignment, Length, Row, Scrollable, Text, TextInput};
+
+use rwallet::config::AppConfig;
+use crate::theme::{ButtonStyle, InputStyle, Palette, RowStyle};
+use crate::{RuWalletMessage, ROBOTO, ROBOTO_BOLD};
+
+#[derive(Debug, Default)]
+pub struct WalletList {
+    scroll: scrollable::State,
+
+    wallet_button_states: Vec<button::State>,
+
+    name_input_state: text_input::State,
+    pub name_input_value: String,
+
+    new_wallet_button_state: button::State,
+    rename_wallet_button_state: button::State,
+    delete_wallet_button_state: button::State,
+
+    // set by Delete, the wallet is only removed once confirmed
+    pub confirm_delete: bool,
+    confirm_delete_button_state: button::State,
+    cancel_delete_button_state: button::State
+}
+
+impl WalletList {
+
+    pub fn view<'a>(&'a mut self, config: &'a AppConfig, palette: Palette) -> Element<'a, RuWalletMessage> {
+
+        let wallet_list_title = Text::new("Wallets")
+            .font(ROBOTO_BOLD)
+            .width(Length::Fill)
+            .size(35)
+            .color(palette.muted)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let mut sidebar = Column::new()
+            .spacing(10)
+            .push(wallet_list_title);
+
+        self.wallet_button_states.resize_with(config.wallets.len(), Default::default);
+
+        for (wallet_config, button_state) in config.wallets.iter().zip(self.wallet_button_states.iter_mut()) {
+
+            let selected = config.selected == Some(wallet_config.id);
+
+            let label = if selected {
+                format!("> {}", wallet_config.name)
+            } else {
+                wallet_config.name.clone()
+            };
+
+            let wallet_button = Button::new(
+                button_state,
+                Text::new(label).font(ROBOTO).size(20)
+            )
+            .width(Length::Fill)
+            .padding(10)
+            .style(RowStyle { palette, selected })
+            .on_press(RuWalletMessage::SelectWallet(wallet_config.id));
+
+            sidebar = sidebar.push(wallet_button);
+        }
+
+        let name_input = TextInput::new(
+            &mut self.name_input_state,
+            "Wallet Name",
+            &mut self.name_input_value,
+            RuWalletMessage::WalletNameInputChanged
+        )
+        .padding(10)
+        .size(20)
+        .font(ROBOTO)
+        .style(InputStyle(palette));
+
+        let mut wallet_actions = Row::new()
+            .spacing(10)
+            .push(
+                Button::new(&mut self.new_wallet_button_state, Text::new("Create"))
+                    .padding(10)
+                    .style(ButtonStyle(palette))
+                    .on_press(RuWalletMessage::NewWallet)
+            );
+
+        if config.selected.is_some() {
+            wallet_actions = wallet_actions
+                .push(
+                    Button::new(&mut self.rename_wallet_button_state, Text::new("Rename"))
+                        .padding(10)
+                        .style(ButtonStyle(palette))
+                        .on_press(RuWalletMessage::RenameWallet)
+                )
+                .push(
+                    Button::new(&mut self.delete_wallet_button_state, Text::new("Delete"))
+                        .padding(10)
+                        .style(ButtonStyle(palette))
+                        .on_press(RuWalletMessage::DeleteWallet)
+                );
+        }
+
+        sidebar = sidebar
+            .push(name_input)
+            .push(wallet_actions);
+
+        if let Some(wallet_config) = config.selected_wallet().filter(|_| self.confirm_delete) {
+            sidebar = sidebar
+                .push(
+                    Text::new(format!("Delete {} and its database? Its descriptors are gone for good unless you have a backup.", wallet_config.name))
+                        .font(ROBOTO)
+                        .size(18)
+                        .color(palette.negative)
+                )
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .push(
+                            Button::new(&mut self.confirm_delete_button_state, Text::new("Delete Wallet"))
+                                .padding(10)
+                                .style(ButtonStyle(palette))
+                                .on_press(RuWalletMessage::ConfirmDeleteWallet)
+                        )
+                        .push(
+                            Button::new(&mut self.cancel_delete_button_state, Text::new("Cancel"))
+                                .padding(10)
+                                .style(ButtonStyle(palette))
+                                .on_press(RuWalletMessage::CancelDeleteWallet)
+                        )
+                );
+        }
+
+        Scrollable::new(&mut self.scroll)
+            .padding(40)
+            .width(Length::Units(350))
+            .push(sidebar)
+            .into()
+    }
+}
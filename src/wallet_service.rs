@@ -0,0 +1,966 @@
+use bdk::bitcoin::consensus::encode::{deserialize, serialize, serialize_hex};
+use bdk::bitcoin::hashes::hex::{FromHex, ToHex};
+use bdk::bitcoin::util::bip32::{ChildNumber, DerivationPath};
+use bdk::bitcoin::util::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::{Address, Network, Script, Transaction, Txid};
+use bdk::blockchain::{noop_progress, ElectrumBlockchain};
+use bdk::database::Database;
+use bdk::descriptor::DescriptorPublicKey;
+use bdk::electrum_client::{self, Client, ElectrumApi};
+use bdk::miniscript::{ForEach, ForEachKey};
+use bdk::sled::Tree;
+use bdk::wallet::AddressIndex;
+use bdk::{Error, FeeRate, KeychainKind, SignOptions, TransactionDetails, Wallet};
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config::WalletConfig;
+use crate::electrum::{self, ElectrumConnector, FailoverConnector, ServerSettings};
+use crate::notifications::confirmations;
+use crate::w_electrum;
+
+#[derive(Debug, Default, Clone)]
+pub struct AddressRow {
+    pub index: u64,
+    pub address: String,
+    pub balance: u64,
+    pub tx_count: u64
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct UTXORow {
+    pub txid: String,
+    pub vout: u32,
+    pub address: String,
+    pub amount: u64,
+    pub height: u32
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TransactionRow {
+    pub txid: String,
+    pub amount: i128,
+    pub fee: Option<u64>,
+    // 0 while unconfirmed
+    pub height: u32,
+    // block time, None while unconfirmed
+    pub timestamp: Option<u64>,
+    // wallet addresses the transaction touches, e.g. "Receive #3"
+    pub labels: Vec<String>
+}
+
+// One transaction of the history export.
+#[derive(Debug, Default, Clone)]
+pub struct HistoryRow {
+    pub txid: String,
+    // block time, None while unconfirmed
+    pub timestamp: Option<u64>,
+    // 0 while unconfirmed
+    pub height: u32,
+    pub confirmations: u32,
+    pub amount: i128,
+    pub fee: Option<u64>,
+    // wallet addresses the transaction touches, e.g. "Receive #3"
+    pub labels: Vec<String>,
+    // recipients of a payment, or senders of a deposit when their outputs are known
+    pub counterparties: Vec<String>
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TxInputRow {
+    pub outpoint: String,
+    // unknown when the previous transaction isn't in the wallet database
+    pub address: Option<String>,
+    pub amount: Option<u64>,
+    pub is_mine: bool
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TxOutputRow {
+    pub vout: u32,
+    pub address: String,
+    pub amount: u64,
+    pub is_mine: bool
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TransactionDetail {
+    pub txid: String,
+    pub amount: i128,
+    pub fee: Option<u64>,
+    // sat/vB
+    pub fee_rate: Option<f32>,
+    pub size: usize,
+    pub vsize: usize,
+    pub weight: usize,
+    pub version: i32,
+    pub lock_time: u32,
+    pub rbf: bool,
+    pub height: u32,
+    pub confirmations: u32,
+    // unix time of the block, None while unconfirmed
+    pub block_time: Option<u64>,
+    pub inputs: Vec<TxInputRow>,
+    pub outputs: Vec<TxOutputRow>,
+    pub raw_hex: String
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AddressHistoryRow {
+    pub txid: String,
+    // 0 or below while unconfirmed
+    pub height: i32,
+    // net effect of the transaction on this address
+    pub amount: i64,
+    pub balance: i64
+}
+
+#[derive(Debug, Clone)]
+pub struct AddressDetail {
+    pub address: String,
+    pub keychain: KeychainKind,
+    pub index: u32,
+    pub derivation_path: String,
+    pub script_type: String,
+    pub script_pubkey: String,
+    // oldest first, mempool transactions last
+    pub history: Vec<AddressHistoryRow>
+}
+
+// Syncs the scripts named by a live notification away from the wallet, which
+// stays with the UI thread and only has to read the database afterwards.
+pub struct ScriptSync {
+    network: Network,
+    servers: Vec<ServerSettings>,
+    database: Tree
+}
+
+impl ScriptSync {
+
+    // stores the new transactions of `scripts` and returns their current history and balance
+    pub fn run(self, scripts: &[Script]) -> Result<Vec<AddressRow>, Error> {
+        let ScriptSync { network, servers, mut database } = self;
+
+        let connector = FailoverConnector::new(servers);
+        let client = connector.client()?;
+
+        let result = w_electrum::sync_scripts(&*client, &mut database, scripts)
+            .and_then(|_| Ok(w_electrum::get_batch_history_and_balance(&*client, &network, scripts)?));
+
+        // the next notification reconnects instead of failing on the same broken client
+        if result.is_err() {
+            connector.invalidate();
+        }
+
+        let rows = result?.into_iter()
+            .map(|aai| AddressRow {
+                index: aai.index,
+                address: aai.address,
+                balance: aai.balance,
+                tx_count: aai.tx_count
+            })
+            .collect();
+
+        Ok(rows)
+    }
+}
+
+// A wallet and the server it syncs with. `WalletService<()>` is a wallet opened
+// offline by `open`, which only answers from its database.
+pub struct WalletService<B = ElectrumBlockchain> {
+    config: WalletConfig,
+    connector: Box<dyn ElectrumConnector>,
+    wallet: Wallet<B, Tree>
+}
+
+impl WalletService {
+
+    // opens (or restores) the wallet described by `config` and syncs it
+    pub fn restore(config: &WalletConfig) -> Result<Self, Error> {
+        Self::restore_with(config, Box::new(FailoverConnector::new(config.server_list())), &config.database_path())
+    }
+
+    // same as `restore`, talking to the server through `connector` instead of the configured
+    // one and keeping the wallet database in `db_path`
+    pub fn restore_with(config: &WalletConfig, connector: Box<dyn ElectrumConnector>, db_path: &Path) -> Result<Self, Error> {
+        let wallet = w_electrum::load_or_create_wallet(
+            connector.as_ref(),
+            &config.network,
+            &config.external_descriptor,
+            &config.internal_descriptor,
+            db_path
+        )?;
+
+        Ok(WalletService {
+            config: config.clone(),
+            connector,
+            wallet
+        })
+    }
+
+    // a second service on the same database with a connection of its own,
+    // so a long sync doesn't hold up the queries answered by this one
+    pub fn sync_handle(&self) -> Result<Self, Error> {
+        let connector: Box<dyn ElectrumConnector> = Box::new(FailoverConnector::new(self.config.server_list()));
+
+        let wallet = Wallet::new(
+            self.config.external_descriptor.as_str(),
+            Some(self.config.internal_descriptor.as_str()),
+            self.config.network,
+            Tree::clone(&self.wallet.database()),
+            ElectrumBlockchain::from(connector.connect()?)
+        )?;
+
+        Ok(WalletService {
+            config: self.config.clone(),
+            connector,
+            wallet
+        })
+    }
+
+    pub fn sync(&self) -> Result<(), Error> {
+        self.wallet.sync(noop_progress(), None)
+    }
+
+    // returns the first `count` addresses of the keychain with their on-chain history
+    pub fn addresses(&self, keychain: KeychainKind, count: u32) -> Result<Vec<AddressRow>, Error> {
+        let mut scripts = Vec::<Script>::new();
+
+        for n in 0..count {
+            let address_info = match keychain {
+                KeychainKind::External => self.wallet.get_address(AddressIndex::Peek(n))?,
+                KeychainKind::Internal => w_electrum::peek_change_address(&self.wallet, n)?,
+            };
+
+            scripts.push(address_info.script_pubkey());
+        }
+
+        let additional_addr_info = self.with_client(|client|
+            w_electrum::get_batch_history_and_balance(client, &self.config.network, &scripts))?;
+
+        let result = additional_addr_info.into_iter()
+            .map(|aai| AddressRow {
+                index: aai.index,
+                address: aai.address,
+                balance: aai.balance,
+                tx_count: aai.tx_count
+            })
+            .collect();
+
+        Ok(result)
+    }
+
+    // tip height as seen by the server
+    pub fn tip_height(&self) -> Result<u32, Error> {
+        self.with_client(|client| client.block_headers_subscribe().map(|header| header.height as u32))
+    }
+
+    // derivation and full history of one wallet address, None if the address isn't ours
+    pub fn address_detail(&self, address: &str) -> Result<Option<AddressDetail>, Error> {
+        let script = match Address::from_str(address) {
+            Ok(address) => address.script_pubkey(),
+            Err(_) => return Ok(None),
+        };
+
+        let (keychain, index) = match self.wallet.database().get_path_from_script_pubkey(&script)? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let mut history_list = self.with_client(|client| client.script_get_history(&script))?;
+
+        // unconfirmed transactions are reported with a height of 0 or -1
+        history_list.sort_by_key(|h| if h.height <= 0 { i32::MAX } else { h.height });
+
+        let mut history = Vec::<AddressHistoryRow>::new();
+        let mut balance = 0i64;
+
+        for item in history_list {
+            let tx = self.get_transaction(&item.tx_hash)?;
+            let mut amount = 0i64;
+
+            for output in tx.output.iter().filter(|output| output.script_pubkey == script) {
+                amount += output.value as i64;
+            }
+
+            // a transaction that paid this address is always in the wallet database
+            for input in &tx.input {
+                let previous_output = self.wallet.database().get_raw_tx(&input.previous_output.txid)?
+                    .and_then(|previous_tx| previous_tx.output.get(input.previous_output.vout as usize).cloned());
+
+                if let Some(previous_output) = previous_output {
+                    if previous_output.script_pubkey == script {
+                        amount -= previous_output.value as i64;
+                    }
+                }
+            }
+
+            balance += amount;
+
+            history.push(AddressHistoryRow {
+                txid: item.tx_hash.to_string(),
+                height: item.height,
+                amount,
+                balance
+            });
+        }
+
+        Ok(Some(AddressDetail {
+            address: address.to_string(),
+            keychain,
+            index,
+            derivation_path: self.derivation_path(keychain, index),
+            script_type: script_type(&script),
+            script_pubkey: script.as_bytes().to_hex(),
+            history
+        }))
+    }
+
+    // asks a second server about the tip and the first addresses of each keychain,
+    // returns the disagreements found (empty when there is no second server)
+    pub fn cross_check(&self, count: u32) -> Result<Vec<String>, Error> {
+        let secondary = match self.connector.secondary_client() {
+            Some(secondary) => secondary?,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut scripts = Vec::<Script>::new();
+
+        for n in 0..count {
+            scripts.push(self.wallet.get_address(AddressIndex::Peek(n))?.script_pubkey());
+            scripts.push(w_electrum::peek_change_address(&self.wallet, n)?.script_pubkey());
+        }
+
+        self.with_client(|client| electrum::cross_check(client, &*secondary, &scripts))
+    }
+
+    pub fn broadcast(&self, tx: &Transaction) -> Result<Txid, Error> {
+        self.wallet.broadcast(tx)
+    }
+
+    // from the wallet database, or from the server for transactions that aren't ours
+    fn get_transaction(&self, txid: &Txid) -> Result<Transaction, Error> {
+        match self.wallet.database().get_raw_tx(txid)? {
+            Some(tx) => Ok(tx),
+            None => self.with_client(|client| client.transaction_get(txid)),
+        }
+    }
+
+    // closes the wallet database and the shared connections to its servers
+    pub fn close(self) {
+        self.connector.close();
+    }
+
+    // runs `f` on the shared client of the server, reconnecting once if the connection broke
+    fn with_client<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        F: Fn(&Client) -> Result<T, electrum_client::Error>
+    {
+        let client = self.connector.client()?;
+
+        match f(&client) {
+            Err(electrum_client::Error::IOError(_)) | Err(electrum_client::Error::SharedIOError(_)) => {
+                self.connector.invalidate();
+                Ok(f(&*self.connector.client()?)?)
+            },
+            result => Ok(result?),
+        }
+    }
+}
+
+impl WalletService<()> {
+
+    // reads the wallet database as the last sync left it, without talking to any server
+    pub fn open(config: &WalletConfig) -> Result<Self, Error> {
+        let wallet = Wallet::new_offline(
+            config.external_descriptor.as_str(),
+            Some(config.internal_descriptor.as_str()),
+            config.network,
+            w_electrum::open_database(&config.database_path())?
+        )?;
+
+        Ok(WalletService {
+            config: config.clone(),
+            connector: Box::new(FailoverConnector::new(config.server_list())),
+            wallet
+        })
+    }
+}
+
+// Everything answered from the wallet database alone.
+impl<B> WalletService<B> {
+
+    pub fn config(&self) -> &WalletConfig {
+        &self.config
+    }
+
+    // a partial sync to run on another thread, see `ScriptSync`
+    pub fn script_sync(&self) -> ScriptSync {
+        ScriptSync {
+            network: self.config.network,
+            servers: self.config.server_list(),
+            database: Tree::clone(&self.wallet.database())
+        }
+    }
+
+    pub fn wallet(&self) -> &Wallet<B, Tree> {
+        &self.wallet
+    }
+
+    pub fn network(&self) -> Network {
+        self.wallet.network()
+    }
+
+    pub fn balance(&self) -> Result<u64, Error> {
+        self.wallet.get_balance()
+    }
+
+    // first wallet address paid by the transaction and its label, e.g. "Receive #3"
+    pub fn receiving_address(&self, txid: &str) -> Result<Option<(String, String)>, Error> {
+        let tx = self.wallet.list_transactions(true)?
+            .into_iter()
+            .find(|tx| tx.txid.to_string() == txid)
+            .and_then(|tx| tx.transaction);
+
+        let tx = match tx {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+
+        let database = self.wallet.database();
+
+        for output in &tx.output {
+            if let Some((keychain, index)) = database.get_path_from_script_pubkey(&output.script_pubkey)? {
+                let address = Address::from_script(&output.script_pubkey, self.config.network)
+                    .map(|a| a.to_string())
+                    .unwrap_or_default();
+
+                return Ok(Some((address, address_label(keychain, index))));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // every script derived so far, on both keychains
+    pub fn watched_scripts(&self) -> Result<Vec<Script>, Error> {
+        self.wallet.database().iter_script_pubkeys(None)
+    }
+
+    pub fn new_address(&self) -> Result<String, Error> {
+        Ok(self.wallet.get_address(AddressIndex::New)?.address.to_string())
+    }
+
+    // most recent transactions first, unconfirmed ones on top
+    pub fn transactions(&self) -> Result<Vec<TransactionRow>, Error> {
+        let mut tx_list = self.wallet.list_transactions(true)?;
+
+        tx_list.sort_by_key(|tx| match &tx.confirmation_time {
+            Some(confirmation_time) => std::cmp::Reverse(confirmation_time.height),
+            None => std::cmp::Reverse(u32::MAX),
+        });
+
+        let mut result = Vec::<TransactionRow>::new();
+
+        for tx in tx_list {
+            let labels = match &tx.transaction {
+                Some(transaction) => self.address_labels(transaction)?,
+                None => Vec::new(),
+            };
+
+            result.push(TransactionRow {
+                txid: tx.txid.to_string(),
+                amount: tx.received as i128 - tx.sent as i128,
+                fee: tx.fee,
+                height: tx.confirmation_time.as_ref().map(|ct| ct.height).unwrap_or(0),
+                timestamp: tx.confirmation_time.as_ref().map(|ct| ct.timestamp),
+                labels
+            });
+        }
+
+        Ok(result)
+    }
+
+    // every transaction with its labels and counterparties, in the order of `transactions`
+    pub fn history(&self, tip_height: u32) -> Result<Vec<HistoryRow>, Error> {
+        let mut tx_list = self.wallet.list_transactions(true)?;
+
+        tx_list.sort_by_key(|tx| match &tx.confirmation_time {
+            Some(confirmation_time) => std::cmp::Reverse(confirmation_time.height),
+            None => std::cmp::Reverse(u32::MAX),
+        });
+
+        let network = self.network();
+        let database = self.wallet.database();
+
+        let mut rows = Vec::<HistoryRow>::new();
+
+        for details in tx_list {
+            let amount = details.received as i128 - details.sent as i128;
+            let height = details.confirmation_time.as_ref().map(|ct| ct.height).unwrap_or(0);
+
+            let mut labels = Vec::<String>::new();
+            let mut counterparties = Vec::<String>::new();
+
+            if let Some(tx) = &details.transaction {
+                // the other side of a payment is on the outputs, the other side of a deposit on the inputs
+                let mut scripts: Vec<(Script, bool)> = tx.output.iter()
+                    .map(|output| (output.script_pubkey.clone(), amount < 0))
+                    .collect();
+
+                for input in &tx.input {
+                    let previous_output = database.get_raw_tx(&input.previous_output.txid)?
+                        .and_then(|previous_tx| previous_tx.output.get(input.previous_output.vout as usize).cloned());
+
+                    if let Some(txout) = previous_output {
+                        scripts.push((txout.script_pubkey, amount > 0));
+                    }
+                }
+
+                for (script, counterparty_side) in scripts {
+                    match database.get_path_from_script_pubkey(&script)? {
+                        Some((keychain, index)) => push_unique(&mut labels, address_label(keychain, index)),
+                        None if counterparty_side => {
+                            if let Some(address) = Address::from_script(&script, network) {
+                                push_unique(&mut counterparties, address.to_string());
+                            }
+                        },
+                        None => {},
+                    }
+                }
+            }
+
+            rows.push(HistoryRow {
+                txid: details.txid.to_string(),
+                timestamp: details.confirmation_time.as_ref().map(|ct| ct.timestamp),
+                height,
+                confirmations: confirmations(height, tip_height),
+                amount,
+                fee: details.fee,
+                labels,
+                counterparties
+            });
+        }
+
+        Ok(rows)
+    }
+
+    // everything known about one wallet transaction, None if the wallet doesn't know `txid`
+    pub fn transaction_detail(&self, txid: &str, tip_height: u32) -> Result<Option<TransactionDetail>, Error> {
+        let details = self.wallet.list_transactions(true)?
+            .into_iter()
+            .find(|tx| tx.txid.to_string() == txid);
+
+        // `include_raw` fills in the transaction itself
+        let (details, tx) = match details {
+            Some(details) => match details.transaction.clone() {
+                Some(tx) => (details, tx),
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+
+        let network = self.network();
+        let database = self.wallet.database();
+
+        let mut inputs = Vec::<TxInputRow>::new();
+
+        for input in &tx.input {
+            let previous_output = database.get_raw_tx(&input.previous_output.txid)?
+                .and_then(|previous_tx| previous_tx.output.get(input.previous_output.vout as usize).cloned());
+
+            let (address, amount, is_mine) = match previous_output {
+                Some(txout) => (
+                    Address::from_script(&txout.script_pubkey, network).map(|a| a.to_string()),
+                    Some(txout.value),
+                    database.get_path_from_script_pubkey(&txout.script_pubkey)?.is_some()
+                ),
+                None => (None, None, false),
+            };
+
+            inputs.push(TxInputRow {
+                outpoint: input.previous_output.to_string(),
+                address,
+                amount,
+                is_mine
+            });
+        }
+
+        let mut outputs = Vec::<TxOutputRow>::new();
+
+        for (vout, output) in tx.output.iter().enumerate() {
+            outputs.push(TxOutputRow {
+                vout: vout as u32,
+                address: Address::from_script(&output.script_pubkey, network).map(|a| a.to_string()).unwrap_or_default(),
+                amount: output.value,
+                is_mine: database.get_path_from_script_pubkey(&output.script_pubkey)?.is_some()
+            });
+        }
+
+        let weight = tx.get_weight();
+        let vsize = (weight + 3) / 4;
+        let height = details.confirmation_time.as_ref().map(|ct| ct.height).unwrap_or(0);
+
+        Ok(Some(TransactionDetail {
+            txid: details.txid.to_string(),
+            amount: details.received as i128 - details.sent as i128,
+            fee: details.fee,
+            fee_rate: details.fee.map(|fee| fee as f32 / vsize as f32),
+            size: serialize(&tx).len(),
+            vsize,
+            weight,
+            version: tx.version,
+            lock_time: tx.lock_time,
+            // BIP125: any input with a sequence below 0xfffffffe opts in
+            rbf: tx.input.iter().any(|input| input.sequence < 0xffff_fffe),
+            height,
+            confirmations: confirmations(height, tip_height),
+            block_time: details.confirmation_time.as_ref().map(|ct| ct.timestamp),
+            inputs,
+            outputs,
+            raw_hex: serialize_hex(&tx)
+        }))
+    }
+
+    pub fn unspent(&self) -> Result<Vec<UTXORow>, Error> {
+        let transactions = self.wallet.list_transactions(false)?;
+
+        let mut result = Vec::<UTXORow>::new();
+
+        for utxo in self.wallet.list_unspent()? {
+
+            let address = Address::from_script(&utxo.txout.script_pubkey, self.network())
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+
+            let height = transactions.iter()
+                .find(|tx| tx.txid == utxo.outpoint.txid)
+                .and_then(|tx| tx.confirmation_time.as_ref())
+                .map(|ct| ct.height)
+                .unwrap_or(0);
+
+            result.push(UTXORow {
+                txid: utxo.outpoint.txid.to_string(),
+                vout: utxo.outpoint.vout,
+                address,
+                amount: utxo.txout.value,
+                height
+            });
+        }
+
+        result.sort_by(|a, b| b.height.cmp(&a.height));
+
+        Ok(result)
+    }
+
+    pub fn create_psbt(&self, recipients: &[(Address, u64)], fee_rate: Option<f32>) -> Result<(PartiallySignedTransaction, TransactionDetails), Error> {
+        let mut builder = self.wallet.build_tx();
+        builder.enable_rbf();
+
+        for (address, amount) in recipients {
+            if address.network != self.network() {
+                return Err(Error::Generic(format!("address {} is not valid for {}", address, self.network())));
+            }
+
+            builder.add_recipient(address.script_pubkey(), *amount);
+        }
+
+        if let Some(fee_rate) = fee_rate {
+            builder.fee_rate(FeeRate::from_sat_per_vb(fee_rate));
+        }
+
+        builder.finish()
+    }
+
+    // returns whether the PSBT is finalized
+    pub fn sign(&self, psbt: &mut PartiallySignedTransaction) -> Result<bool, Error> {
+        self.wallet.sign(psbt, SignOptions::default())
+    }
+
+    // labels of the wallet addresses `tx` spends from or pays to, outputs first
+    fn address_labels(&self, tx: &Transaction) -> Result<Vec<String>, Error> {
+        let database = self.wallet.database();
+        let mut scripts: Vec<Script> = tx.output.iter().map(|output| output.script_pubkey.clone()).collect();
+
+        for input in &tx.input {
+            let previous_output = database.get_raw_tx(&input.previous_output.txid)?
+                .and_then(|previous_tx| previous_tx.output.get(input.previous_output.vout as usize).cloned());
+
+            if let Some(txout) = previous_output {
+                scripts.push(txout.script_pubkey);
+            }
+        }
+
+        let mut labels = Vec::<String>::new();
+
+        for script in scripts {
+            if let Some((keychain, index)) = database.get_path_from_script_pubkey(&script)? {
+                push_unique(&mut labels, address_label(keychain, index));
+            }
+        }
+
+        Ok(labels)
+    }
+
+    // full path of the first key of the descriptor, e.g. m/84'/1'/0'/0/5
+    fn derivation_path(&self, keychain: KeychainKind, index: u32) -> String {
+        let descriptor = self.wallet.get_descriptor_for_keychain(keychain);
+        let mut path = Vec::<ChildNumber>::new();
+
+        descriptor.for_any_key(|key| {
+            if let ForEach::Key(DescriptorPublicKey::XPub(xkey)) = key {
+                if let Some((_, origin)) = &xkey.origin {
+                    path.extend(origin.as_ref().iter().cloned());
+                }
+                path.extend(xkey.derivation_path.as_ref().iter().cloned());
+            }
+            true
+        });
+
+        path.push(ChildNumber::Normal { index });
+
+        DerivationPath::from(path).to_string()
+    }
+}
+
+// copies the history and balance of the changed addresses onto their rows, leaving the others untouched
+pub fn refresh_addresses(rows: &mut [AddressRow], changed: &[AddressRow]) {
+    for change in changed {
+        if let Some(row) = rows.iter_mut().find(|row| row.address == change.address) {
+            row.balance = change.balance;
+            row.tx_count = change.tx_count;
+        }
+    }
+}
+
+// "Receive #3" or "Change #1"
+pub fn address_label(keychain: KeychainKind, index: u32) -> String {
+    match keychain {
+        KeychainKind::External => format!("Receive #{}", index),
+        KeychainKind::Internal => format!("Change #{}", index),
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+impl<B> fmt::Debug for WalletService<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalletService")
+            .field("config", &self.config)
+            .field("url", &self.connector.url())
+            .finish()
+    }
+}
+
+// e.g. P2WPKH, from the shape of the scriptPubKey
+fn script_type(script: &Script) -> String {
+    let script_type = if script.is_v0_p2wpkh() {
+        "P2WPKH"
+    } else if script.is_v0_p2wsh() {
+        "P2WSH"
+    } else if script.is_p2sh() {
+        "P2SH"
+    } else if script.is_p2pkh() {
+        "P2PKH"
+    } else if script.is_witness_program() {
+        "Witness program"
+    } else {
+        "Non-standard"
+    };
+
+    script_type.to_string()
+}
+
+// accepts either a base64 PSBT or a raw transaction in hex
+pub fn parse_transaction(input: &str) -> Result<Transaction, String> {
+    if let Ok(psbt) = PartiallySignedTransaction::from_str(input) {
+        // extracting a partially signed PSBT gives a transaction with empty scripts
+        let unsigned = psbt.inputs.iter()
+            .filter(|input| input.final_script_sig.is_none() && input.final_script_witness.is_none())
+            .count();
+
+        if unsigned > 0 {
+            return Err(format!("PSBT is not finalized: {} of {} inputs have no final scriptSig or witness", unsigned, psbt.inputs.len()));
+        }
+
+        return Ok(psbt.extract_tx());
+    }
+
+    let bytes = Vec::<u8>::from_hex(input).map_err(|_| "input is neither a PSBT nor a raw transaction")?;
+
+    deserialize(&bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use bdk::bitcoin::hashes::Hash;
+    use bdk::bitcoin::util::bip32::ExtendedPrivKey;
+    use bdk::bitcoin::{OutPoint, TxIn, TxOut, WPubkeyHash};
+    use bdk::database::MemoryDatabase;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::mock_electrum::{MockChain, MockElectrumServer};
+
+    fn descriptors() -> (String, String) {
+        let xprv = ExtendedPrivKey::new_master(Network::Regtest, &[7u8; 32]).unwrap();
+
+        (format!("wpkh({}/84'/1'/0'/0/*)", xprv), format!("wpkh({}/84'/1'/0'/1/*)", xprv))
+    }
+
+    // n-th address of `descriptor`, derived without touching any server
+    fn peek_script(descriptor: &str, n: u32) -> Script {
+        let wallet = Wallet::new_offline(descriptor, None, Network::Regtest, MemoryDatabase::new()).unwrap();
+
+        wallet.get_address(AddressIndex::Peek(n)).unwrap().script_pubkey()
+    }
+
+    fn payment(inputs: &[OutPoint], outputs: &[(&Script, u64)]) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: inputs.iter()
+                .map(|previous_output| TxIn {
+                    previous_output: *previous_output,
+                    script_sig: Script::new(),
+                    sequence: 0xffff_ffff,
+                    witness: Vec::new()
+                })
+                .collect(),
+            output: outputs.iter()
+                .map(|(script_pubkey, value)| TxOut { value: *value, script_pubkey: (*script_pubkey).clone() })
+                .collect()
+        }
+    }
+
+    // a deposit to Receive #0, spent to pay someone else with change to Change #0,
+    // and an unconfirmed deposit to Receive #1
+    fn scripted_chain() -> (MockChain, [Transaction; 3]) {
+        let (external, internal) = descriptors();
+        let receive_0 = peek_script(&external, 0);
+        let receive_1 = peek_script(&external, 1);
+        let change_0 = peek_script(&internal, 0);
+        let stranger = Script::new_v0_wpkh(&WPubkeyHash::hash(b"stranger"));
+
+        let mut chain = MockChain::new(110);
+
+        let funding = payment(&[OutPoint::null()], &[(&stranger, 200_000)]);
+        let deposit = payment(&[OutPoint::new(funding.txid(), 0)], &[(&receive_0, 100_000), (&stranger, 99_000)]);
+        let spend = payment(&[OutPoint::new(deposit.txid(), 0)], &[(&stranger, 30_000), (&change_0, 69_000)]);
+        let pending = payment(&[OutPoint::new(deposit.txid(), 1)], &[(&receive_1, 5_000), (&stranger, 93_000)]);
+
+        chain.add_history(&stranger, &funding, 99);
+        chain.add_history(&receive_0, &deposit, 100);
+        chain.add_history(&receive_0, &spend, 101);
+        chain.add_history(&change_0, &spend, 101);
+        chain.add_history(&receive_1, &pending, 0);
+
+        chain.set_balance(&change_0, 69_000, 0);
+        chain.set_balance(&receive_1, 0, 5_000);
+
+        (chain, [deposit, spend, pending])
+    }
+
+    // a wallet on the mock server, its database in `directory` rather than the user's wallets
+    fn restore(server: &MockElectrumServer, directory: &TempDir) -> WalletService {
+        let (external, internal) = descriptors();
+
+        let mut config = WalletConfig::new(0, "mock");
+        config.external_descriptor = external;
+        config.internal_descriptor = internal;
+        config.network = Network::Regtest;
+        config.electrum_url = server.url();
+
+        WalletService::restore_with(&config, server.connector(), directory.path()).unwrap()
+    }
+
+    #[test]
+    fn restore_finds_addresses() {
+        let (chain, _) = scripted_chain();
+        let server = MockElectrumServer::start(chain).unwrap();
+        let directory = TempDir::new().unwrap();
+        let service = restore(&server, &directory);
+
+        let (external, internal) = descriptors();
+        let receiving = service.addresses(KeychainKind::External, 3).unwrap();
+        let change = service.addresses(KeychainKind::Internal, 1).unwrap();
+
+        let address = |script: Script| Address::from_script(&script, Network::Regtest).unwrap().to_string();
+
+        assert_eq!(receiving[0].address, address(peek_script(&external, 0)));
+        assert_eq!(receiving[1].address, address(peek_script(&external, 1)));
+        assert_eq!(change[0].address, address(peek_script(&internal, 0)));
+
+        assert_eq!(receiving.iter().map(|row| row.tx_count).collect::<Vec<_>>(), vec![2, 1, 0]);
+        assert_eq!(change[0].tx_count, 1);
+        assert_eq!(change[0].balance, 69_000);
+    }
+
+    #[test]
+    fn restore_finds_transactions() {
+        let (chain, [deposit, spend, pending]) = scripted_chain();
+        let server = MockElectrumServer::start(chain).unwrap();
+        let directory = TempDir::new().unwrap();
+        let service = restore(&server, &directory);
+
+        let transactions = service.transactions().unwrap();
+        let summary: Vec<(String, i128, u32)> = transactions.iter()
+            .map(|tx| (tx.txid.clone(), tx.amount, tx.height))
+            .collect();
+
+        assert_eq!(summary, vec![
+            (pending.txid().to_string(), 5_000, 0),
+            (spend.txid().to_string(), -31_000, 101),
+            (deposit.txid().to_string(), 100_000, 100),
+        ]);
+
+        assert_eq!(transactions[1].fee, Some(1_000));
+        assert_eq!(transactions[0].timestamp, None);
+        assert_eq!(transactions[2].timestamp, Some(server.chain().lock().unwrap().header(100).time as u64));
+    }
+
+    #[test]
+    fn restore_finds_unspent_outputs() {
+        let (chain, [_, spend, pending]) = scripted_chain();
+        let server = MockElectrumServer::start(chain).unwrap();
+        let directory = TempDir::new().unwrap();
+        let service = restore(&server, &directory);
+
+        let unspent: Vec<(String, u32, u64, u32)> = service.unspent().unwrap().into_iter()
+            .map(|utxo| (utxo.txid, utxo.vout, utxo.amount, utxo.height))
+            .collect();
+
+        assert_eq!(unspent, vec![
+            (spend.txid().to_string(), 1, 69_000, 101),
+            (pending.txid().to_string(), 0, 5_000, 0),
+        ]);
+
+        assert_eq!(service.balance().unwrap(), 74_000);
+    }
+
+    #[test]
+    fn mock_server_lists_unspent_outputs() {
+        let (chain, [_, spend, _]) = scripted_chain();
+        let server = MockElectrumServer::start(chain).unwrap();
+        let client = Client::new(&server.url()).unwrap();
+
+        let (external, internal) = descriptors();
+
+        // the deposit to Receive #0 was spent, the change wasn't
+        assert!(client.script_list_unspent(&peek_script(&external, 0)).unwrap().is_empty());
+
+        let change = client.script_list_unspent(&peek_script(&internal, 0)).unwrap();
+
+        assert_eq!(change.len(), 1);
+        assert_eq!(change[0].tx_hash, spend.txid());
+        assert_eq!(change[0].tx_pos, 1);
+        assert_eq!(change[0].value, 69_000);
+        assert_eq!(change[0].height, 101);
+    }
+}
@@ -0,0 +1,67 @@
+use bdk::bitcoin::Script;
+use bdk::electrum_client::{Client, ElectrumApi, Error};
+
+use crate::electrum::ElectrumConnector;
+
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    // a new block was found at this height
+    NewTip(u32),
+    // the status (history) of these scripts changed
+    ScriptsChanged(Vec<Script>),
+    // the subscription connection broke, the watcher has to be subscribed again
+    Disconnected(String)
+}
+
+// Keeps a dedicated connection subscribed to the chain tip and to the wallet
+// scripts. The server pushes notifications, `poll` collects them.
+pub struct ChainWatcher {
+    client: Client,
+    scripts: Vec<Script>
+}
+
+impl ChainWatcher {
+
+    // subscribes to the tip and every script, returns the watcher and the current tip height
+    pub fn subscribe(connector: &dyn ElectrumConnector, scripts: Vec<Script>) -> Result<(Self, u32), Error> {
+        // notifications are delivered on the connection that subscribed, so don't share it
+        let client = connector.connect()?;
+
+        let tip = client.block_headers_subscribe()?;
+
+        for script in &scripts {
+            client.script_subscribe(script)?;
+        }
+
+        Ok((ChainWatcher { client, scripts }, tip.height as u32))
+    }
+
+    pub fn poll(&self) -> Result<Vec<ChainEvent>, Error> {
+        // the client only reads pending notifications while waiting for a response
+        self.client.ping()?;
+
+        let mut events = Vec::<ChainEvent>::new();
+
+        let mut tip = None;
+        while let Some(header) = self.client.block_headers_pop()? {
+            tip = Some(header.height as u32);
+        }
+
+        if let Some(height) = tip {
+            events.push(ChainEvent::NewTip(height));
+        }
+
+        let mut changed = Vec::<Script>::new();
+        for script in &self.scripts {
+            if self.client.script_pop(script)?.is_some() {
+                changed.push(script.clone());
+            }
+        }
+
+        if !changed.is_empty() {
+            events.push(ChainEvent::ScriptsChanged(changed));
+        }
+
+        Ok(events)
+    }
+}